@@ -1,8 +1,14 @@
 //! Atomically install a regular file or a symlink to destination,
 //! can be either noclobber (fail if destination already exists) or
 //! replacing it atomically if it exists.
+//!
+//! Multiple installations can be grouped in a [`Transaction`], so that
+//! they can be reverted together if any of them fails.
 
-use std::{fs, io, path::Path};
+use std::{
+    fs, io, mem,
+    path::{Path, PathBuf},
+};
 
 use reflink_copy::reflink_or_copy;
 use tempfile::{NamedTempFile, TempPath};
@@ -183,6 +189,154 @@ fn persist(temp_path: TempPath, to: &Path) -> io::Result<()> {
     }
 }
 
+/// Records files about to be replaced or created, so that they can be
+/// restored to their original state if the installation fails.
+///
+/// Call [`Transaction::prepare`] before installing to a destination.
+/// Once everything is installed, call [`Transaction::commit`] to discard
+/// the backups. If the transaction is dropped without being committed,
+/// all recorded destinations are restored in reverse order.
+#[derive(Debug, Default)]
+pub struct Transaction {
+    entries: Vec<TransactionEntry>,
+    /// Files to remove on commit.
+    removals: Vec<PathBuf>,
+}
+
+#[derive(Debug)]
+struct TransactionEntry {
+    dst: PathBuf,
+    /// `None` if `dst` did not exist before.
+    backup: Option<TempPath>,
+}
+
+impl Transaction {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Backup `dst` if it exists, so that it can be restored on rollback.
+    ///
+    /// If `dst` does not exist, it will be removed on rollback.
+    ///
+    /// This is a blocking function, must be called in `block_in_place` mode.
+    pub fn prepare(&mut self, dst: &Path) -> io::Result<()> {
+        let backup = match fs::symlink_metadata(dst) {
+            Ok(_) => Some(backup_file(dst)?),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => return Err(err),
+        };
+
+        self.entries.push(TransactionEntry {
+            dst: dst.to_owned(),
+            backup,
+        });
+
+        Ok(())
+    }
+
+    /// Remove `dst` once the transaction is committed, it is left untouched
+    /// on rollback.
+    pub fn remove_on_commit(&mut self, dst: &Path) {
+        self.removals.push(dst.to_owned());
+    }
+
+    /// Move all entries of `other` into `self`, so that they are committed
+    /// or rolled back together.
+    pub fn extend(&mut self, mut other: Transaction) {
+        self.entries.append(&mut other.entries);
+        self.removals.append(&mut other.removals);
+    }
+
+    /// Keep all installed files, remove the backups and the files passed to
+    /// [`Transaction::remove_on_commit`].
+    ///
+    /// This is a blocking function, must be called in `block_in_place` mode.
+    pub fn commit(mut self) {
+        // Dropping `TempPath` removes the backup
+        drop(mem::take(&mut self.entries));
+
+        for dst in mem::take(&mut self.removals) {
+            debug!("Removing '{}'", dst.display());
+            match fs::remove_file(&dst) {
+                Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                    warn!("Failed to remove '{}': {err}", dst.display())
+                }
+                _ => (),
+            }
+        }
+    }
+
+    /// Restore all recorded destinations to their original state.
+    ///
+    /// This is a blocking function, must be called in `block_in_place` mode.
+    pub fn rollback(mut self) -> io::Result<()> {
+        self.rollback_inner()
+    }
+
+    fn rollback_inner(&mut self) -> io::Result<()> {
+        let mut res = Ok(());
+
+        self.removals.clear();
+
+        // Restore in reverse order, in case the same `dst` is
+        // prepared more than once.
+        while let Some(TransactionEntry { dst, backup }) = self.entries.pop() {
+            let ret = if let Some(backup) = backup {
+                debug!("Restoring '{}' from '{}'", dst.display(), backup.display());
+                persist(backup, &dst)
+            } else {
+                debug!("Removing '{}'", dst.display());
+                match fs::remove_file(&dst) {
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+                    res => res,
+                }
+            };
+
+            if let Err(err) = ret {
+                warn!("Failed to restore '{}': {err}", dst.display());
+                res = res.and(Err(err));
+            }
+        }
+
+        res
+    }
+}
+
+impl Drop for Transaction {
+    fn drop(&mut self) {
+        if !self.entries.is_empty() {
+            warn!("Installation is not completed, restoring replaced files");
+            let _ = self.rollback_inner();
+        }
+    }
+}
+
+/// Create a backup of `path` in the same directory, so that it can be
+/// restored by renaming.
+fn backup_file(path: &Path) -> io::Result<TempPath> {
+    let parent = parent(path)?;
+
+    debug!("Creating tempPath at '{}'", parent.display());
+    let temp_path = NamedTempFile::new_in(parent)?.into_temp_path();
+    fs::remove_file(&temp_path)?;
+
+    debug!(
+        "Backing up '{}' to '{}'",
+        path.display(),
+        temp_path.display()
+    );
+
+    // Hard link is cheap and preserves the original file even after
+    // `path` is atomically replaced, fallback to copy if it fails.
+    if let Err(err) = fs::hard_link(path, &temp_path) {
+        debug!("Failed to hard link: {err}, fallback to copy");
+        reflink_or_copy(path, &temp_path)?;
+    }
+
+    Ok(temp_path)
+}
+
 #[cfg(windows)]
 mod win {
     use std::{os::windows::ffi::OsStrExt, path::Path};
@@ -217,3 +371,75 @@ mod win {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use tempfile::TempDir;
+
+    #[test]
+    fn transaction_rollback() {
+        let dir = TempDir::new().unwrap();
+        let existing = dir.path().join("existing");
+        let created = dir.path().join("created");
+        let src = dir.path().join("src");
+
+        fs::write(&existing, "old").unwrap();
+
+        let mut transaction = Transaction::new();
+
+        transaction.prepare(&existing).unwrap();
+        fs::write(&src, "new").unwrap();
+        atomic_install(&src, &existing).unwrap();
+
+        transaction.prepare(&created).unwrap();
+        fs::write(&src, "new").unwrap();
+        atomic_install(&src, &created).unwrap();
+
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "new");
+
+        transaction.rollback().unwrap();
+
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "old");
+        assert!(!created.exists());
+        // Only the restored file is left
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn transaction_commit() {
+        let dir = TempDir::new().unwrap();
+        let existing = dir.path().join("existing");
+        let src = dir.path().join("src");
+
+        fs::write(&existing, "old").unwrap();
+        fs::write(&src, "new").unwrap();
+
+        let mut transaction = Transaction::new();
+        transaction.prepare(&existing).unwrap();
+        atomic_install(&src, &existing).unwrap();
+        transaction.commit();
+
+        assert_eq!(fs::read_to_string(&existing).unwrap(), "new");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn transaction_remove_on_commit() {
+        let dir = TempDir::new().unwrap();
+        let stale = dir.path().join("stale");
+
+        fs::write(&stale, "stale").unwrap();
+
+        let mut transaction = Transaction::new();
+        transaction.remove_on_commit(&stale);
+        transaction.rollback().unwrap();
+        assert!(stale.exists());
+
+        let mut transaction = Transaction::new();
+        transaction.remove_on_commit(&stale);
+        transaction.commit();
+        assert!(!stale.exists());
+    }
+}
@@ -11,7 +11,7 @@ use binstalk::{
     helpers::remote,
     manifests::cargo_toml_binstall::PkgFmt,
     ops::resolve::{CrateName, VersionReqExt},
-    registry::Registry,
+    registry::{Registry, ReleaseAge},
 };
 use binstalk_manifests::cargo_toml_binstall::{PkgOverride, Strategy};
 use clap::{builder::PossibleValue, error::ErrorKind, CommandFactory, Parser, ValueEnum};
@@ -108,6 +108,26 @@ pub struct Args {
     )]
     pub(crate) bin: Option<Vec<CompactString>>,
 
+    /// Install a binary under a different name.
+    ///
+    /// Syntax: `--rename old=new`
+    ///
+    /// This cannot be used when multiple packages are specified, and takes
+    /// precedence over the `renames` in the settings file.
+    ///
+    /// The package must have a binary named `old`, and no two binaries can be
+    /// installed under the same name.
+    ///
+    /// The new name is recorded in the metadata files, so it is kept when the package
+    /// is upgraded and `cargo uninstall` removes the renamed binary.
+    #[clap(
+        help_heading = "Package selection",
+        long,
+        value_name = "OLD=NEW",
+        action = clap::ArgAction::Append
+    )]
+    pub(crate) rename: Vec<BinRename>,
+
     /// Override Cargo.toml package manifest path.
     ///
     /// This skips searching crates.io for a manifest and uses the specified path directly, useful
@@ -245,9 +265,32 @@ pub struct Args {
     /// This flag is now enabled by default thus a no-op.
     ///
     /// By default, Binstall will install a binary as-is in the install path.
+    /// Use `--versioned` to install versioned binaries with symlinks instead.
     #[clap(help_heading = "Options", long, default_value_t = true)]
     pub(crate) no_symlinks: bool,
 
+    /// Install binaries side by side with other versions of the same package.
+    ///
+    /// Binaries are installed as `<bin>-v<version>` and `<bin>` is created as a
+    /// symlink pointing to it, so previously installed versions are kept and can
+    /// be switched back to with `--use`.
+    #[clap(help_heading = "Options", long)]
+    pub(crate) versioned: bool,
+
+    /// Switch the specified packages to a version installed with `--versioned`.
+    ///
+    /// Syntax: `--use crate@version`
+    ///
+    /// The `<bin>` symlinks are updated to point to the highest installed version
+    /// matching the version requirement, and the metadata files are updated
+    /// accordingly, without accessing the network.
+    #[clap(
+        help_heading = "Options",
+        long = "use",
+        conflicts_with_all = ["version_req", "dry_run", "rollback", "versioned"]
+    )]
+    pub(crate) use_version: bool,
+
     /// Dry run, fetch and show changes without installing binaries.
     #[clap(help_heading = "Options", long)]
     pub(crate) dry_run: bool,
@@ -269,6 +312,51 @@ pub struct Args {
     #[clap(help_heading = "Options", long)]
     pub(crate) continue_on_failure: bool,
 
+    /// Run every installed binary with `--version` and check that it succeeds
+    /// and prints the installed version.
+    ///
+    /// The command can be customised with `smoke_test` in the settings file.
+    /// If the check fails, the binaries of the package are rolled back.
+    #[clap(help_heading = "Options", long)]
+    pub(crate) smoke_test: bool,
+
+    /// Reject packages whose binaries are dynamically linked against shared
+    /// libraries that are not found on the host.
+    ///
+    /// By default, binstall only warns about the missing libraries.
+    /// The next available package is tried instead, e.g. a statically linked one
+    /// or the one for the musl target.
+    #[clap(help_heading = "Options", long)]
+    pub(crate) strict_shared_libs: bool,
+
+    /// Refuse to install crate versions affected by RustSec advisories,
+    /// including crates marked as unmaintained.
+    ///
+    /// This enables checking the advisories, which are by default fetched from
+    /// the RustSec advisory database, unless configured in the settings.
+    /// Without this flag, the advisories found when checking is enabled in the
+    /// settings are only warned about.
+    #[clap(help_heading = "Options", long)]
+    pub(crate) deny_advisories: bool,
+
+    /// Allow installing a version that has been yanked from the registry.
+    ///
+    /// This is only honoured when an exact version is requested,
+    /// e.g. `--version =1.2.3` or `crate@=1.2.3`.
+    #[clap(help_heading = "Options", long)]
+    pub(crate) allow_yanked: bool,
+
+    /// Ignore versions published less than this long ago, e.g. `7d`.
+    ///
+    /// Accepts a number followed by `w`, `d`, `h` or `m` (weeks, days, hours or
+    /// minutes), a number without unit being in days.
+    /// Versions requested exactly with `=x.y.z` are not affected.
+    ///
+    /// Whether versions without publish time in the registry index are
+    /// installed can be configured with `missing_pubtime` in the settings.
+    #[clap(help_heading = "Options", long, value_name = "AGE")]
+    pub(crate) min_release_age: Option<ReleaseAge>,
+
     /// By default, binstall keeps track of the installed packages with metadata files
     /// stored in the installation root directory.
     ///
@@ -334,6 +422,10 @@ pub struct Args {
     /// `CARGO_REGISTRIES_{registry_name}_INDEX` for the index URL. If that is not
     /// set, it falls back to the `registries.<name>.index` key in `.cargo/config.toml`.
     ///
+    /// Source replacement configured with `replace-with` in the `[source]` table of
+    /// `.cargo/config.toml` is honoured, e.g. to use a mirror of crates.io, or a
+    /// `local-registry` or `directory` of vendored crates to work offline.
+    ///
     /// Cannot be combined with `--index`.
     #[clap(
         help_heading = "Options",
@@ -353,6 +445,17 @@ pub struct Args {
     #[clap(hide(true), long)]
     pub(crate) secure: bool,
 
+    /// Restore the previously installed version of the specified packages.
+    ///
+    /// Before upgrading or reinstalling a package, binstall keeps a backup of its
+    /// binaries in the installation root directory, up to `keep_backups` (set in the
+    /// settings file, defaults to 1) per package.
+    ///
+    /// This option restores the most recent backup and updates the metadata files
+    /// accordingly, without accessing the network.
+    #[clap(help_heading = "Options", long, conflicts_with_all = ["version_req", "dry_run"])]
+    pub(crate) rollback: bool,
+
     /// Force a crate to be installed even if it is already installed.
     #[clap(help_heading = "Options", long)]
     pub(crate) force: bool,
@@ -475,6 +578,35 @@ pub struct Args {
     pub(crate) markdown_help: bool,
 }
 
+#[derive(Debug, Clone)]
+pub(crate) struct BinRename {
+    pub(crate) from: CompactString,
+    pub(crate) to: CompactString,
+}
+
+impl FromStr for BinRename {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((from, to)) = s.split_once('=') else {
+            return Err(format!("expected `old=new`, found `{s}`"));
+        };
+
+        if from.is_empty() || to.is_empty() {
+            return Err("binary name cannot be empty".to_owned());
+        }
+
+        if to.contains(['/', '\\']) {
+            return Err(format!("`{to}` must be a file name, not a path"));
+        }
+
+        Ok(Self {
+            from: from.into(),
+            to: to.into(),
+        })
+    }
+}
+
 #[derive(Debug, Clone)]
 pub(crate) struct GithubToken(pub(crate) Zeroizing<Box<str>>);
 
@@ -614,6 +746,8 @@ pub fn parse() -> (Args, PkgOverride) {
     if opts.crate_names.len() > 1 {
         let option = if opts.version_req.is_some() {
             "version"
+        } else if !opts.rename.is_empty() {
+            "rename"
         } else if opts.manifest_path.is_some() {
             "manifest-path"
         } else {
@@ -750,6 +884,17 @@ mod test {
         Args::command().debug_assert()
     }
 
+    #[test]
+    fn parse_bin_rename() {
+        let rename: BinRename = "rg=ripgrep".parse().unwrap();
+        assert_eq!(rename.from, "rg");
+        assert_eq!(rename.to, "ripgrep");
+
+        "rg".parse::<BinRename>().unwrap_err();
+        "rg=".parse::<BinRename>().unwrap_err();
+        "rg=../ripgrep".parse::<BinRename>().unwrap_err();
+    }
+
     #[test]
     fn quickinstall_url_matches() {
         let long_help = Args::command()
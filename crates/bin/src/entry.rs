@@ -1,15 +1,17 @@
 use std::{
-    env, fs,
+    collections::BTreeMap,
+    env, fs, io,
     path::{Path, PathBuf},
     sync::Arc,
     time::Duration,
 };
 
-use atomic_file_install::atomic_install;
+use atomic_file_install::{atomic_install, atomic_symlink_file, Transaction};
 use binstalk::{
+    bins::{installed_ext, versioned_file_name},
     errors::{BinstallError, CrateContextError},
     fetchers::{Fetcher, GhCrateMeta, QuickInstall, SignaturePolicy},
-    get_desired_targets,
+    get_desired_targets_with_emulated,
     helpers::{
         jobserver_client::LazyJobserverClient,
         lazy_gh_api_client::LazyGhApiClient,
@@ -18,26 +20,37 @@ use binstalk::{
     },
     ops::{
         self,
+        advisories::{AdvisoryDb, AdvisoryDbSource},
+        license_policy::LicensePolicy,
         resolve::{CrateName, Resolution, ResolutionFetch, VersionReqExt},
         CargoTomlFetchOverride, Options, Resolver,
     },
+    registry::{DirectoryRegistry, LocalRegistry, Registry, VersionFilter},
     TARGET,
 };
 use binstalk_manifests::{
+    binstall_backups::{Backup, Backups},
+    cargo_config::{SourceReplacementKind, CRATES_IO_SOURCE},
     cargo_toml_binstall::{PkgOverride, Strategy},
     crate_info::{CrateInfo, CrateSource},
-    crates_manifests::Manifests,
+    crates_manifests::{Manifests, ManifestsError},
 };
-use compact_str::CompactString;
+use compact_str::{CompactString, ToCompactString};
 use file_format::FileFormat;
 use log::LevelFilter;
-use miette::{Report, Result};
+use miette::{bail, IntoDiagnostic, Report, Result, WrapErr};
 use semver::{Version, VersionReq};
 use tokio::task::block_in_place;
 use tracing::{debug, info, warn};
 
 use crate::registry_auth::{get_registry_env_var, resolve_registry_auth};
-use crate::{args::Args, gh_token, git_credentials, initialise::Init, ui::confirm};
+use crate::{
+    args::{Args, BinRename},
+    gh_token, git_credentials,
+    hooks::run_post_install_hooks,
+    initialise::Init,
+    ui::confirm,
+};
 
 pub fn install_crates(
     args: Args,
@@ -50,6 +63,7 @@ pub fn install_crates(
         settings,
         cargo_root,
         install_path,
+        custom_install_path,
         manifests,
         temp_dir,
     } = crate::initialise::initialise(&args)?;
@@ -68,6 +82,13 @@ pub fn install_crates(
         })
         .collect();
 
+    let bin_renames = collect_bin_renames(
+        &args.crate_names,
+        args.rename,
+        settings.renames,
+        manifests.as_ref(),
+    );
+
     // Remove installed crates
     let mut crate_names = filter_out_installed_crates(
         args.crate_names,
@@ -82,8 +103,50 @@ pub fn install_crates(
         return Ok(None);
     }
 
+    let policy = &settings.policy;
+    let license_policy = if policy.allowed_licenses.is_empty() && policy.denied_licenses.is_empty()
+    {
+        None
+    } else {
+        Some(
+            LicensePolicy::new(&policy.allowed_licenses, &policy.denied_licenses)
+                .into_diagnostic()
+                .wrap_err("invalid license in `[policy]` of the settings")?,
+        )
+    };
+
+    let smoke_test = if settings.smoke_test.enabled {
+        Some(ops::smoke_test::SmokeTest::new(
+            settings.smoke_test.command,
+            settings.smoke_test.check_version,
+            Duration::from_secs(settings.smoke_test.timeout_secs),
+        )?)
+    } else {
+        None
+    };
+
+    let advisories = settings.advisories;
+    let advisory_db = if !advisories.enabled {
+        None
+    } else if let Some(db_path) = advisories.db_path {
+        Some(AdvisoryDb::new(AdvisoryDbSource::Path(db_path)))
+    } else {
+        #[cfg(feature = "git")]
+        {
+            let db_url = advisories
+                .db_url
+                .parse::<binstalk::registry::GitUrl>()
+                .into_diagnostic()
+                .wrap_err("invalid `advisories.db_url` in the settings")?;
+            Some(AdvisoryDb::new(AdvisoryDbSource::Git(db_url)))
+        }
+        #[cfg(not(feature = "git"))]
+        bail!("fetching the advisory database requires the git feature, set `advisories.db_path` in the settings to a local checkout instead")
+    };
+
     // Launch target detection
-    let desired_targets = get_desired_targets(settings.targets);
+    let desired_targets =
+        get_desired_targets_with_emulated(settings.targets, settings.emulated_targets);
 
     // Initialize reqwest client
     let rate_limit = args.rate_limit;
@@ -147,7 +210,9 @@ pub fn install_crates(
                     .and_then(|registry| registry.default)
             });
 
-            let registry = if let Some(registry_name) = registry_name.as_deref() {
+            // Name of the source in `[source]` of the cargo config, used to
+            // look up its replacement.
+            let (registry, source_name) = if let Some(registry_name) = registry_name.as_deref() {
                 let index = get_registry_env_var(registry_name, "INDEX");
 
                 let index = index
@@ -155,39 +220,145 @@ pub fn install_crates(
                     .or_else(|| cargo_config.get_registry_index(registry_name));
 
                 if let Some(index) = index {
-                    index.parse().map_err(BinstallError::from)?
+                    (
+                        index.parse().map_err(BinstallError::from)?,
+                        cargo_config.get_source_name_by_index(index),
+                    )
                 } else if registry_name.eq_ignore_ascii_case("crates-io") {
-                    Default::default()
+                    (Default::default(), Some(CRATES_IO_SOURCE))
                 } else {
                     return Err(BinstallError::UnknownRegistryName(registry_name.into()).into());
                 }
             } else {
-                Default::default()
+                (Default::default(), Some(CRATES_IO_SOURCE))
             };
 
-            let registry_auth = resolve_registry_auth(
-                &cargo_config,
-                cargo_home,
-                registry_name.as_deref(),
-                &registry,
-            );
+            let replacement = match source_name {
+                Some(source_name) => cargo_config.get_source_replacement(source_name)?,
+                None => None,
+            };
 
-            (
-                binstalk::registry::ResolvedRegistry::new(registry, registry_auth),
-                registry_name.filter(|name| !name.eq_ignore_ascii_case("crates-io")),
-                None,
-            )
+            if let Some(replacement) = replacement {
+                debug!(
+                    "Using `{}` replacing registry {registry} as configured in `[source]`",
+                    replacement.name
+                );
+
+                let (
+                    replacement_registry,
+                    registry_auth,
+                    cargo_install_registry,
+                    cargo_install_index,
+                ) = match replacement.kind {
+                    SourceReplacementKind::Registry(index) => {
+                        let replacement_registry: Registry =
+                            index.parse().map_err(BinstallError::from)?;
+                        // Only `[registries]` have credentials.
+                        let registry_auth = resolve_registry_auth(
+                            &cargo_config,
+                            cargo_home,
+                            Some(replacement.name),
+                            &replacement_registry,
+                        );
+
+                        (
+                            replacement_registry,
+                            registry_auth,
+                            Some(replacement.name.into()),
+                            None,
+                        )
+                    }
+                    SourceReplacementKind::Index(index) => {
+                        let replacement_registry: Registry =
+                            index.parse().map_err(BinstallError::from)?;
+                        let registry_auth = resolve_registry_auth(
+                            &cargo_config,
+                            cargo_home,
+                            None,
+                            &replacement_registry,
+                        );
+                        let cargo_install_index =
+                            replacement_registry.cargo_install_index_arg().into();
+
+                        (
+                            replacement_registry,
+                            registry_auth,
+                            None,
+                            Some(cargo_install_index),
+                        )
+                    }
+                    // `cargo install` cannot be pointed at these, it uses
+                    // them through the same source replacement.
+                    SourceReplacementKind::LocalRegistry(path) => (
+                        Registry::Local(Arc::new(LocalRegistry::new(path.into()))),
+                        None,
+                        None,
+                        None,
+                    ),
+                    SourceReplacementKind::Directory(path) => (
+                        Registry::Directory(Arc::new(DirectoryRegistry::new(path.into()))),
+                        None,
+                        None,
+                        None,
+                    ),
+                };
+
+                (
+                    binstalk::registry::ResolvedRegistry::new(replacement_registry, registry_auth)
+                        .replacing(registry),
+                    cargo_install_registry,
+                    cargo_install_index,
+                )
+            } else {
+                let registry_auth = resolve_registry_auth(
+                    &cargo_config,
+                    cargo_home,
+                    registry_name.as_deref(),
+                    &registry,
+                );
+
+                (
+                    binstalk::registry::ResolvedRegistry::new(registry, registry_auth),
+                    registry_name.filter(|name| !name.eq_ignore_ascii_case("crates-io")),
+                    None,
+                )
+            }
         }
     };
 
+    let resolved_registry = if settings.cache.enabled {
+        let cache_dir = settings.cache.dir.unwrap_or_else(|| {
+            cargo_home
+                .as_deref()
+                .unwrap_or(&cargo_root)
+                .join("binstall")
+                .join("cache")
+        });
+        resolved_registry.with_cache_dir(&cache_dir)
+    } else {
+        resolved_registry
+    };
+
     // Create binstall_opts
     let binstall_opts = Arc::new(Options {
-        no_symlinks: args.no_symlinks,
+        no_symlinks: args.no_symlinks && !args.versioned,
         dry_run: args.dry_run,
         force: args.force,
         quiet: args.log_level == Some(LevelFilter::Off),
         locked: args.locked,
         no_track: !settings.track_installs,
+        smoke_test,
+        strict_shared_libs: settings.strict_shared_libs,
+        license_policy,
+        advisory_db,
+        deny_advisories: advisories.deny,
+        version_filter: VersionFilter {
+            allow_yanked: args.allow_yanked,
+            min_release_age: settings.policy.min_release_age,
+            missing_pubtime: settings.policy.missing_pubtime,
+        },
+        wasi_runtime: settings.wasi.runtime,
+        wasi_runtime_flags: settings.wasi.flags,
 
         #[cfg(feature = "git")]
         cargo_toml_fetch_override: match (args.manifest_path, args.git) {
@@ -208,10 +379,12 @@ pub fn install_crates(
             bins.sort_unstable();
             bins
         }),
+        bin_renames,
 
         temp_dir: temp_dir.path().to_owned(),
         install_path,
         has_overriden_install_path: args.install_path.is_some(),
+        extra_files_root: (!custom_install_path).then(|| cargo_root.clone()),
         cargo_root: Some(cargo_root),
         cargo_install_registry,
         cargo_install_index,
@@ -240,6 +413,8 @@ pub fn install_crates(
     let no_confirm = !settings.confirm;
     let no_cleanup = args.no_cleanup;
     let continue_on_failure = settings.continue_on_failure;
+    let keep_backups = settings.keep_backups;
+    let post_install_hooks = settings.post_install_hooks;
 
     // Resolve crates
     let tasks = crate_names
@@ -297,15 +472,36 @@ pub fn install_crates(
                 }
             }
 
+            let staged_backups = if dry_run {
+                StagedBackups::default()
+            } else {
+                backup_installed_crates(
+                    &resolution_fetches,
+                    manifests.as_ref(),
+                    &binstall_opts.install_path,
+                    keep_backups,
+                )
+            };
+
             let manifest_update_res = do_install_fetches_continue_on_failure(
                 resolution_fetches,
                 manifests,
+                staged_backups,
                 &binstall_opts,
                 dry_run,
                 temp_dir,
                 no_cleanup,
                 &mut errors,
-            );
+            )
+            .map(|installed| {
+                errors.extend(block_in_place(|| {
+                    run_post_install_hooks(
+                        &post_install_hooks,
+                        &installed,
+                        &binstall_opts.install_path,
+                    )
+                }));
+            });
 
             let tasks: Vec<_> = resolution_sources
                 .into_iter()
@@ -359,15 +555,34 @@ pub fn install_crates(
                 confirm().await?;
             }
 
-            do_install_fetches(
+            let staged_backups = if dry_run {
+                StagedBackups::default()
+            } else {
+                backup_installed_crates(
+                    &resolution_fetches,
+                    manifests.as_ref(),
+                    &binstall_opts.install_path,
+                    keep_backups,
+                )
+            };
+
+            let installed = do_install_fetches(
                 resolution_fetches,
                 manifests,
+                staged_backups,
                 &binstall_opts,
                 dry_run,
                 temp_dir,
                 no_cleanup,
             )?;
 
+            let hook_errors = block_in_place(|| {
+                run_post_install_hooks(&post_install_hooks, &installed, &binstall_opts.install_path)
+            });
+            if let Some(err) = BinstallError::crate_errors(hook_errors) {
+                return Err(err.into());
+            }
+
             let tasks: Vec<_> = resolution_sources
                 .into_iter()
                 .map(|source| AutoAbortJoinHandle::spawn(source.install(binstall_opts.clone())))
@@ -478,80 +693,255 @@ fn filter_out_installed_crates<'a>(
     })
 }
 
+/// Collect binaries to install under a different name for each crate.
+///
+/// Renames specified on the command line take precedence over the settings,
+/// which take precedence over renames recorded for the installed crate.
+fn collect_bin_renames(
+    crate_names: &[CrateName],
+    cli_renames: Vec<BinRename>,
+    mut settings_renames: BTreeMap<CompactString, BTreeMap<CompactString, CompactString>>,
+    manifests: Option<&Manifests>,
+) -> BTreeMap<CompactString, BTreeMap<CompactString, CompactString>> {
+    crate_names
+        .iter()
+        .filter_map(|crate_name| {
+            let name = &crate_name.name;
+
+            let mut renames = manifests
+                .and_then(|manifests| manifests.get(name))
+                .map(|crate_info| crate_info.bin_renames.clone())
+                .unwrap_or_default();
+            renames.extend(settings_renames.remove(name).into_iter().flatten());
+            renames.extend(
+                cli_renames
+                    .iter()
+                    .map(|rename| (rename.from.clone(), rename.to.clone())),
+            );
+
+            (!renames.is_empty()).then(|| (name.clone(), renames))
+        })
+        .collect()
+}
+
+/// Backups of crates about to be upgraded or reinstalled, so that they can
+/// be restored with `--rollback`.
+///
+/// Outdated backups are only removed by [`StagedBackups::commit`] once the
+/// installation is committed, new backups are removed if this is dropped
+/// without being committed, e.g. because the installation failed.
+#[derive(Default)]
+struct StagedBackups {
+    backups: Option<Backups>,
+    keep: usize,
+    /// Crates to remove outdated backups of on commit.
+    names: Vec<CompactString>,
+    staged: Vec<Backup>,
+}
+
+impl StagedBackups {
+    /// Remove the backup of crate `name`, which failed to install.
+    ///
+    /// This is a blocking function.
+    fn discard(&mut self, name: &str) {
+        self.names.retain(|staged_name| staged_name != name);
+        if let Some(index) = self
+            .staged
+            .iter()
+            .position(|backup| backup.crate_info.name == name)
+        {
+            remove_backup(self.staged.swap_remove(index));
+        }
+    }
+
+    /// Keep the new backups and remove the oldest ones, so that at most
+    /// `keep` remains for each crate.
+    ///
+    /// This is a blocking function.
+    fn commit(mut self) {
+        self.staged.clear();
+
+        if let Some(backups) = &self.backups {
+            for name in &self.names {
+                if let Err(err) = backups.prune(name, self.keep) {
+                    warn!("Failed to remove outdated backups of {name}: {err}");
+                }
+            }
+        }
+    }
+}
+
+impl Drop for StagedBackups {
+    fn drop(&mut self) {
+        for backup in self.staged.drain(..) {
+            remove_backup(backup);
+        }
+    }
+}
+
+fn remove_backup(backup: Backup) {
+    let name = backup.crate_info.name.clone();
+    if let Err(err) = backup.remove() {
+        warn!("Failed to remove backup of {name}: {err}");
+    }
+}
+
+/// Backup binaries of crates about to be upgraded or reinstalled.
+fn backup_installed_crates(
+    resolution_fetches: &[Box<ResolutionFetch>],
+    manifests: Option<&Manifests>,
+    install_path: &Path,
+    keep_backups: usize,
+) -> StagedBackups {
+    let Some(manifests) = manifests else {
+        return StagedBackups::default();
+    };
+
+    let mut staged_backups = StagedBackups {
+        backups: Some(manifests.backups().clone()),
+        keep: keep_backups,
+        names: Vec::new(),
+        staged: Vec::new(),
+    };
+
+    block_in_place(|| {
+        for fetch in resolution_fetches {
+            staged_backups.names.push(fetch.name.clone());
+
+            if keep_backups == 0 {
+                continue;
+            }
+
+            match manifests.backup(&fetch.name, install_path) {
+                Ok(backup) => staged_backups.staged.extend(backup),
+                Err(err) => warn!(
+                    "Failed to backup previously installed {}: {err}",
+                    fetch.name
+                ),
+            }
+        }
+    });
+
+    staged_backups
+}
+
 #[allow(clippy::vec_box)]
 fn do_install_fetches(
     resolution_fetches: Vec<Box<ResolutionFetch>>,
     // Take manifests by value to drop the `FileLock`.
     manifests: Option<Manifests>,
+    staged_backups: StagedBackups,
     binstall_opts: &Options,
     dry_run: bool,
     temp_dir: tempfile::TempDir,
     no_cleanup: bool,
-) -> Result<()> {
+) -> Result<Vec<CrateInfo>> {
     if resolution_fetches.is_empty() {
-        return Ok(());
+        return Ok(Vec::new());
     }
 
     if dry_run {
         info!("Dry-run: Not proceeding to install fetched binaries");
-        return Ok(());
+        return Ok(Vec::new());
     }
 
     block_in_place(|| {
+        // Replaced binaries are restored if `transaction` is dropped without
+        // being committed, so that the install root is never half-upgraded.
+        let mut transaction = Transaction::new();
+
         let metadata_vec = resolution_fetches
             .into_iter()
-            .map(|fetch| fetch.install(binstall_opts))
+            .map(|fetch| fetch.install(binstall_opts, &mut transaction))
             .collect::<Result<Vec<_>, BinstallError>>()?;
 
-        update_manifest(manifests, temp_dir, no_cleanup, metadata_vec)
+        update_manifest(
+            manifests,
+            &mut transaction,
+            temp_dir,
+            no_cleanup,
+            metadata_vec.clone(),
+        )?;
+        transaction.commit();
+        staged_backups.commit();
+
+        Ok(metadata_vec)
     })
 }
 
-#[allow(clippy::vec_box)]
+#[allow(clippy::vec_box, clippy::too_many_arguments)]
 fn do_install_fetches_continue_on_failure(
     resolution_fetches: Vec<Box<ResolutionFetch>>,
     // Take manifests by value to drop the `FileLock`.
     manifests: Option<Manifests>,
+    mut staged_backups: StagedBackups,
     binstall_opts: &Options,
     dry_run: bool,
     temp_dir: tempfile::TempDir,
     no_cleanup: bool,
     errors: &mut Vec<Box<CrateContextError>>,
-) -> Result<()> {
+) -> Result<Vec<CrateInfo>> {
     if resolution_fetches.is_empty() {
-        return Ok(());
+        return Ok(Vec::new());
     }
 
     if dry_run {
         info!("Dry-run: Not proceeding to install fetched binaries");
-        return Ok(());
+        return Ok(Vec::new());
     }
 
     block_in_place(|| {
+        let mut transaction = Transaction::new();
+
         let metadata_vec = resolution_fetches
             .into_iter()
-            .filter_map(|fetch| match fetch.install(binstall_opts) {
-                Ok(crate_info) => Some(crate_info),
-                Err(BinstallError::CrateContext(err)) => {
-                    errors.push(err);
-                    None
+            .filter_map(|fetch| {
+                // Each crate gets its own transaction, so that a failed crate
+                // is reverted without affecting the others.
+                let mut crate_transaction = Transaction::new();
+                let name = fetch.name.clone();
+
+                match fetch.install(binstall_opts, &mut crate_transaction) {
+                    Ok(crate_info) => {
+                        transaction.extend(crate_transaction);
+                        Some(crate_info)
+                    }
+                    Err(BinstallError::CrateContext(err)) => {
+                        if let Err(restore_err) = crate_transaction.rollback() {
+                            warn!("Failed to restore replaced binaries: {restore_err}");
+                        }
+                        staged_backups.discard(&name);
+                        errors.push(err);
+                        None
+                    }
+                    Err(e) => panic!("Expected BinstallError::CrateContext(_), got {e}"),
                 }
-                Err(e) => panic!("Expected BinstallError::CrateContext(_), got {e}"),
             })
             .collect::<Vec<_>>();
 
-        update_manifest(manifests, temp_dir, no_cleanup, metadata_vec)
+        update_manifest(
+            manifests,
+            &mut transaction,
+            temp_dir,
+            no_cleanup,
+            metadata_vec.clone(),
+        )?;
+        transaction.commit();
+        staged_backups.commit();
+
+        Ok(metadata_vec)
     })
 }
 
 fn update_manifest(
     manifests: Option<Manifests>,
+    transaction: &mut Transaction,
     temp_dir: tempfile::TempDir,
     no_cleanup: bool,
     metadata_vec: Vec<CrateInfo>,
 ) -> Result<()> {
     if let Some(manifests) = manifests {
-        manifests.update(metadata_vec)?;
+        update_manifests(manifests, transaction, metadata_vec)?;
     }
 
     if no_cleanup {
@@ -566,6 +956,164 @@ fn update_manifest(
     Ok(())
 }
 
+/// Update `manifests`, with extra files no longer provided by the crates
+/// removed once `transaction` is committed.
+fn update_manifests(
+    manifests: Manifests,
+    transaction: &mut Transaction,
+    metadata_vec: Vec<CrateInfo>,
+) -> Result<()> {
+    for extra_file in manifests.stale_extra_files(&metadata_vec)? {
+        transaction.remove_on_commit(&extra_file);
+    }
+
+    manifests.update(metadata_vec)?;
+
+    Ok(())
+}
+
+pub fn rollback_crates(args: Args) -> Result<()> {
+    let Init {
+        install_path,
+        manifests,
+        ..
+    } = crate::initialise::initialise(&args)?;
+
+    let Some(manifests) = manifests else {
+        bail!("--rollback requires installs to be tracked, it cannot be used with --no-track or --install-path");
+    };
+
+    // Binaries are restored if `transaction` is dropped without being
+    // committed, e.g. if a crate has no backup.
+    let mut transaction = Transaction::new();
+    let mut metadata_vec = Vec::new();
+    let mut backups = Vec::new();
+
+    for crate_name in CrateName::dedup(args.crate_names) {
+        let name = crate_name.name;
+
+        let backup = manifests
+            .backups()
+            .latest(&name)
+            .map_err(ManifestsError::from)?
+            .ok_or_else(|| BinstallError::NoBackup(name.clone()))?;
+        let crate_info = backup.crate_info.clone();
+
+        info!("Rolling back {name} to v{}", crate_info.current_version);
+
+        for bin in &crate_info.bins {
+            let src = backup.bin_path(bin);
+            if src.try_exists().map_err(BinstallError::from)? {
+                let dst = install_path.join(bin);
+                transaction.prepare(&dst).map_err(BinstallError::from)?;
+                atomic_install(&src, &dst).map_err(BinstallError::from)?;
+            }
+        }
+
+        // Remove binaries only provided by the version being rolled back
+        if let Some(curr_crate_info) = manifests.get(&name) {
+            for bin in &curr_crate_info.bins {
+                if !crate_info.bins.contains(bin) {
+                    let dst = install_path.join(bin);
+                    transaction.prepare(&dst).map_err(BinstallError::from)?;
+                    match fs::remove_file(&dst) {
+                        Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                            return Err(BinstallError::from(err).into())
+                        }
+                        _ => (),
+                    }
+                }
+            }
+        }
+
+        backups.push(backup);
+        metadata_vec.push(crate_info);
+    }
+
+    update_manifests(manifests, &mut transaction, metadata_vec)?;
+    transaction.commit();
+
+    // The backups are consumed by the rollback
+    backups.into_iter().for_each(remove_backup);
+
+    Ok(())
+}
+
+pub fn use_crate_versions(args: Args) -> Result<()> {
+    let Init {
+        install_path,
+        manifests,
+        ..
+    } = crate::initialise::initialise(&args)?;
+
+    let Some(manifests) = manifests else {
+        bail!("--use requires installs to be tracked, it cannot be used with --no-track or --install-path");
+    };
+
+    let mut metadata_vec = Vec::new();
+
+    for crate_name in CrateName::dedup(args.crate_names) {
+        let name = crate_name.name;
+        let version_req = crate_name.version_req.unwrap_or(VersionReq::STAR);
+
+        let Some((mut crate_info, version)) = manifests.get(&name).and_then(|crate_info| {
+            let version = crate_info
+                .installed_versions
+                .iter()
+                .rev()
+                .find(|version| version_req.matches(version))?;
+            Some((crate_info.clone(), version.clone()))
+        }) else {
+            return Err(BinstallError::VersionNotInstalled {
+                crate_name: name,
+                version_req: version_req.to_compact_string(),
+            }
+            .into());
+        };
+
+        info!("Switching {name} to v{version}");
+
+        let installed_ext = installed_ext(&crate_info.target);
+        let version_str = version.to_compact_string();
+
+        for bin in &crate_info.bins {
+            let base_name = match bin.strip_suffix(installed_ext) {
+                Some(base_name) => base_name,
+                // Launchers of WASI modules run the unversioned module
+                // symlink, so there is nothing to switch.
+                None if crate_info.wasi_runtime.is_some() => continue,
+                None => bin,
+            };
+            let versioned = versioned_file_name(base_name, &version_str, installed_ext);
+
+            if !install_path
+                .join(&versioned)
+                .try_exists()
+                .map_err(BinstallError::from)?
+            {
+                warn!("{name} v{version} does not provide binary {bin}, skipping it");
+                continue;
+            }
+
+            // Use relative symlinks on unix, same as `BinFile::install_link`
+            let dest = if cfg!(target_family = "unix") {
+                PathBuf::from(versioned)
+            } else {
+                install_path.join(versioned)
+            };
+
+            atomic_symlink_file(&dest, &install_path.join(bin)).map_err(BinstallError::from)?;
+        }
+
+        crate_info.current_version = version;
+        metadata_vec.push(crate_info);
+    }
+
+    manifests.update(metadata_vec)?;
+
+    Ok(())
+}
+
 pub fn self_install(args: Args) -> Result<()> {
     let Init {
         install_path,
@@ -593,6 +1141,12 @@ pub fn self_install(args: Args) -> Result<()> {
             source: CrateSource::cratesio_registry(),
             target: CompactString::const_new(TARGET),
             bins: vec![CompactString::const_new("cargo-binstall")],
+            installed_versions: Vec::new(),
+            bin_renames: Default::default(),
+            wasi_runtime: None,
+            license: None,
+            yanked: false,
+            extra_files: Vec::new(),
         }])?;
     }
 
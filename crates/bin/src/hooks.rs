@@ -0,0 +1,162 @@
+//! Post-install hooks configured in the settings.
+//!
+//! Hooks are only ever read from the settings of the user, never from crate
+//! metadata, since they run arbitrary commands.
+
+use std::{
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+use binstalk::errors::{BinstallError, CrateContextError};
+use binstalk_manifests::crate_info::CrateInfo;
+use tracing::{info, warn};
+
+use crate::settings::PostInstallHook;
+
+/// Run the hooks matching each installed crate, in order.
+///
+/// Failures of required hooks are returned with the crate as context,
+/// others are only logged.
+///
+/// This is a blocking function.
+#[allow(clippy::vec_box)]
+pub(crate) fn run_post_install_hooks(
+    hooks: &[PostInstallHook],
+    crate_infos: &[CrateInfo],
+    install_path: &Path,
+) -> Vec<Box<CrateContextError>> {
+    let mut errors = Vec::new();
+
+    for crate_info in crate_infos {
+        for hook in hooks {
+            if !hook.crates.is_empty() && !hook.crates.contains(&crate_info.name) {
+                continue;
+            }
+
+            if let Err(err) = run_hook(hook, crate_info, install_path) {
+                if !hook.required {
+                    warn!("Post-install hook for {} failed: {err}", crate_info.name);
+                } else if let BinstallError::CrateContext(err) =
+                    err.crate_context(crate_info.name.clone())
+                {
+                    errors.push(err);
+                }
+            }
+        }
+    }
+
+    errors
+}
+
+fn run_hook(
+    hook: &PostInstallHook,
+    crate_info: &CrateInfo,
+    install_path: &Path,
+) -> Result<(), BinstallError> {
+    let Some((program, args)) = hook.command.split_first() else {
+        return Ok(());
+    };
+    let command = hook.command.join(" ");
+
+    info!(
+        "Running post-install hook for {}: {command}",
+        crate_info.name
+    );
+
+    let json = serde_json::to_string(crate_info).expect("CrateInfo is always serializable");
+
+    let mut child = Command::new(&**program)
+        .args(args.iter().map(|arg| &**arg))
+        .env("BINSTALL_CRATE_INFO", &json)
+        .env("BINSTALL_CRATE_NAME", &*crate_info.name)
+        .env(
+            "BINSTALL_CRATE_VERSION",
+            crate_info.current_version.to_string(),
+        )
+        .env("BINSTALL_BINS", crate_info.bins.join(" "))
+        .env("BINSTALL_INSTALL_PATH", install_path)
+        // Do not leak tokens to hooks via environment variables.
+        .env_remove("GITHUB_TOKEN")
+        .env_remove("GH_TOKEN")
+        .stdin(Stdio::piped())
+        .spawn()?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // The hook might not read stdin at all and exit early.
+        stdin.write_all(json.as_bytes()).ok();
+    }
+
+    let status = child.wait()?;
+    if status.success() {
+        Ok(())
+    } else {
+        Err(BinstallError::SubProcess {
+            command: command.into_boxed_str(),
+            status,
+        })
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use std::fs;
+
+    use binstalk_manifests::crate_info::CrateSource;
+    use semver::Version;
+
+    use super::*;
+
+    fn hook(script: &str, required: bool) -> PostInstallHook {
+        PostInstallHook {
+            command: vec!["sh".into(), "-c".into(), script.into()],
+            crates: Vec::new(),
+            required,
+        }
+    }
+
+    #[test]
+    fn run_hooks() {
+        let dir = tempfile::tempdir().unwrap();
+        let out = dir.path().join("out.json");
+        let crate_info = CrateInfo {
+            name: "foo".into(),
+            version_req: "*".into(),
+            current_version: Version::new(1, 2, 3),
+            source: CrateSource::cratesio_registry(),
+            target: "x86_64-unknown-linux-gnu".into(),
+            bins: vec!["foo".into(), "bar".into()],
+            installed_versions: Vec::new(),
+            bin_renames: Default::default(),
+            wasi_runtime: None,
+            license: None,
+            yanked: false,
+            extra_files: Vec::new(),
+        };
+
+        let script = format!(
+            r#"test "$BINSTALL_CRATE_NAME $BINSTALL_CRATE_VERSION $BINSTALL_BINS" = "foo 1.2.3 foo bar" && cat > '{}'"#,
+            out.display()
+        );
+        let errors = run_post_install_hooks(
+            &[hook(&script, true), hook("exit 1", false)],
+            std::slice::from_ref(&crate_info),
+            dir.path(),
+        );
+        assert!(errors.is_empty());
+
+        let json: serde_json::Value = serde_json::from_slice(&fs::read(&out).unwrap()).unwrap();
+        assert_eq!(json["name"], "foo");
+        assert_eq!(json["current_version"], "1.2.3");
+
+        let mut other_crate = hook("exit 1", true);
+        other_crate.crates = vec!["bar".into()];
+        let errors = run_post_install_hooks(
+            &[hook("exit 1", true), other_crate],
+            &[crate_info],
+            dir.path(),
+        );
+        assert_eq!(errors.len(), 1);
+    }
+}
@@ -15,6 +15,8 @@ pub(crate) struct Init {
     pub(crate) settings: crate::settings::Settings,
     pub(crate) cargo_root: PathBuf,
     pub(crate) install_path: PathBuf,
+    /// The install path is not `<cargo_root>/bin`, installs are not tracked.
+    pub(crate) custom_install_path: bool,
     pub(crate) manifests: Option<Manifests>,
     pub(crate) temp_dir: TempDir,
 }
@@ -147,6 +149,7 @@ pub(crate) fn initialise(args: &Args) -> Result<Init> {
         settings,
         cargo_root,
         install_path,
+        custom_install_path,
         manifests,
         temp_dir,
     })
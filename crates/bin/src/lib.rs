@@ -5,6 +5,7 @@ mod bin_util;
 mod entry;
 mod gh_token;
 mod git_credentials;
+mod hooks;
 mod initialise;
 mod logging;
 mod main_impl;
@@ -53,10 +53,15 @@ rustc-llvm-version: {rustc_llvm_version}"#
 
         let start = Instant::now();
 
-        let jobserver_client = LazyJobserverClient::new();
+        let result = if args.rollback {
+            entry::rollback_crates(args)
+        } else if args.use_version {
+            entry::use_crate_versions(args)
+        } else {
+            let jobserver_client = LazyJobserverClient::new();
 
-        let result =
-            run_tokio_main(|| entry::install_crates(args, cli_overrides, jobserver_client));
+            run_tokio_main(|| entry::install_crates(args, cli_overrides, jobserver_client))
+        };
 
         let done = start.elapsed();
         debug!("run time: {done:?}");
@@ -1,9 +1,15 @@
 use std::{
-    env, io,
+    collections::HashMap,
+    env,
+    io::{self, BufRead, BufReader, Write},
     path::Path,
-    process::{Command, Stdio},
+    process::{Child, Command, Stdio},
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
+#[cfg(feature = "asymmetric-token")]
+use binstalk::registry::AsymmetricKey;
 use binstalk::registry::{Registry, RegistryAuth};
 use binstalk_manifests::{
     cargo_config::{Config as CargoConfig, CredentialProvider},
@@ -11,11 +17,19 @@ use binstalk_manifests::{
 };
 use binstalk_types::SecretString;
 use compact_str::CompactString;
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+#[cfg(not(feature = "asymmetric-token"))]
+use tracing::warn;
 
 #[derive(Clone, Debug, Eq, PartialEq)]
 enum SupportedRegistryCredentialProvider {
     CargoToken,
     CargoTokenFromStdout(Vec<CompactString>),
+    CargoPaseto,
+    /// External provider speaking cargo's credential provider protocol,
+    /// the executable followed by its arguments.
+    CredentialProcess(Vec<CompactString>),
 }
 
 fn normalize_registry_name(value: &str) -> String {
@@ -55,6 +69,10 @@ fn resolve_supported_provider(
         return (provider.len() == 1).then_some(SupportedRegistryCredentialProvider::CargoToken);
     }
 
+    if provider_name == "cargo:paseto" {
+        return (provider.len() == 1).then_some(SupportedRegistryCredentialProvider::CargoPaseto);
+    }
+
     if provider_name == "cargo:token-from-stdout" {
         return (provider.len() > 1).then_some(
             SupportedRegistryCredentialProvider::CargoTokenFromStdout(provider[1..].to_vec()),
@@ -69,16 +87,19 @@ fn resolve_supported_provider(
         return None;
     }
 
-    if provider.len() != 1 {
-        return None;
+    if provider.len() == 1 {
+        if let Some(alias) = cargo_config.credential_alias.get(provider_name.as_str()) {
+            seen_aliases.push(provider_name.clone());
+            let supports =
+                resolve_supported_provider_from_config(cargo_config, alias, seen_aliases);
+            seen_aliases.pop();
+            return supports;
+        }
     }
 
-    let provider = cargo_config.credential_alias.get(provider_name.as_str())?;
-
-    seen_aliases.push(provider_name.clone());
-    let supports = resolve_supported_provider_from_config(cargo_config, provider, seen_aliases);
-    seen_aliases.pop();
-    supports
+    Some(SupportedRegistryCredentialProvider::CredentialProcess(
+        provider.to_vec(),
+    ))
 }
 
 pub(crate) fn get_registry_env_var(name: &str, suffix: &str) -> Option<String> {
@@ -162,6 +183,38 @@ fn resolve_cargo_token(
     None
 }
 
+#[cfg(feature = "asymmetric-token")]
+fn resolve_cargo_paseto(
+    cargo_credentials: &Credentials,
+    registry_name: Option<&str>,
+    registry: &Registry,
+) -> Option<AsymmetricKey> {
+    let registry_name = registry_name?;
+
+    let (secret_key, subject) = match get_registry_env_var(registry_name, "SECRET_KEY") {
+        Some(secret_key) => (
+            SecretString::from_boxed_str(secret_key.into_boxed_str()),
+            get_registry_env_var(registry_name, "SECRET_KEY_SUBJECT").map(CompactString::from),
+        ),
+        None => (
+            cargo_credentials
+                .get_registry_secret_key(registry_name)?
+                .clone(),
+            cargo_credentials
+                .get_registry_secret_key_subject(registry_name)
+                .map(CompactString::from),
+        ),
+    };
+
+    // Cargo signs the index url of the registry, with the `sparse+` prefix.
+    AsymmetricKey::new(
+        &secret_key,
+        subject,
+        registry.cargo_install_index_arg().into(),
+    )
+    .ok()
+}
+
 fn resolve_provider_command_arg(arg: &str, index_url: &str) -> String {
     // Cargo's `BasicProcessCredential` replaces `{index_url}` before spawning `cargo:token-from-stdout` commands.
     // https://github.com/rust-lang/cargo/blob/master/src/cargo/util/credential/adaptor.rs#L27-L34
@@ -237,6 +290,218 @@ fn resolve_cargo_token_from_stdout(
     Ok(buffer)
 }
 
+/// Version of cargo's credential provider protocol.
+/// https://doc.rust-lang.org/cargo/reference/credential-provider-protocol.html
+const CREDENTIAL_PROTOCOL_VERSION: u32 = 1;
+
+/// Tokens returned by credential providers and cached as directed by them,
+/// keyed by the index url of the registry.
+static CREDENTIAL_PROCESS_CACHE: Lazy<Mutex<HashMap<String, CachedToken>>> =
+    Lazy::new(Default::default);
+
+/// Cached tokens expiring sooner than this are not used, like cargo does.
+const TOKEN_EXPIRATION_MARGIN: Duration = Duration::from_secs(60);
+
+struct CachedToken {
+    token: SecretString,
+    /// Unix timestamp after which the token must not be used.
+    expiration: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct CredentialHello {
+    v: Vec<u32>,
+}
+
+#[derive(Serialize)]
+struct CredentialRequest<'a> {
+    v: u32,
+    registry: CredentialRegistryInfo<'a>,
+    kind: &'static str,
+    operation: &'static str,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    args: &'a [CompactString],
+}
+
+#[derive(Serialize)]
+struct CredentialRegistryInfo<'a> {
+    #[serde(rename = "index-url")]
+    index_url: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+}
+
+#[derive(Deserialize)]
+struct CredentialGetResponse {
+    token: Box<str>,
+    #[serde(flatten)]
+    cache: CredentialCacheControl,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "cache", rename_all = "kebab-case")]
+enum CredentialCacheControl {
+    Never,
+    Session,
+    Expires { expiration: u64 },
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+enum CredentialError {
+    UrlNotSupported,
+    NotFound,
+    OperationNotSupported,
+    Other { message: String },
+}
+
+fn unix_timestamp(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or_default()
+}
+
+fn get_cached_token(index_url: &str) -> Option<SecretString> {
+    let mut cache = CREDENTIAL_PROCESS_CACHE.lock().unwrap();
+    let cached = cache.get(index_url)?;
+
+    let deadline = unix_timestamp(SystemTime::now() + TOKEN_EXPIRATION_MARGIN);
+    if cached
+        .expiration
+        .is_some_and(|expiration| expiration < deadline)
+    {
+        cache.remove(index_url);
+        None
+    } else {
+        Some(cached.token.clone())
+    }
+}
+
+/// Send a `get` request to the credential provider `child` and read its
+/// response, then close its stdin so that it exits.
+fn request_credential(
+    child: &mut Child,
+    executable: &str,
+    index_url: &str,
+    registry_name: Option<&str>,
+    args: &[CompactString],
+) -> io::Result<Result<CredentialGetResponse, CredentialError>> {
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap());
+
+    let mut line = String::new();
+    stdout.read_line(&mut line)?;
+    let hello: CredentialHello = serde_json::from_str(&line)?;
+    if !hello.v.contains(&CREDENTIAL_PROTOCOL_VERSION) {
+        return Err(io::Error::other(format!(
+            "credential provider `{executable}` does not support protocol version {CREDENTIAL_PROTOCOL_VERSION}",
+        )));
+    }
+
+    let request = CredentialRequest {
+        v: CREDENTIAL_PROTOCOL_VERSION,
+        registry: CredentialRegistryInfo {
+            index_url,
+            name: registry_name,
+        },
+        kind: "get",
+        operation: "read",
+        args,
+    };
+    serde_json::to_writer(&mut stdin, &request)?;
+    stdin.write_all(b"\n")?;
+    stdin.flush()?;
+
+    let mut line = SecretString::from_string(String::new());
+    stdout.read_line(&mut line)?;
+
+    // The provider exits once its stdin is closed.
+    drop(stdin);
+
+    Ok(serde_json::from_str(&line)?)
+}
+
+fn resolve_credential_process(
+    provider_args: &[CompactString],
+    registry_name: Option<&str>,
+    registry: &Registry,
+) -> io::Result<SecretString> {
+    let Some((executable, args)) = provider_args.split_first() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "The credential provider must be a command",
+        ));
+    };
+    let index_url = registry.cargo_install_index_arg();
+
+    if let Some(token) = get_cached_token(&index_url) {
+        return Ok(token);
+    }
+
+    let mut child = Command::new(executable.as_str())
+        .arg("--cargo-plugin")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    let response = match request_credential(&mut child, executable, &index_url, registry_name, args)
+    {
+        Ok(response) => response,
+        Err(err) => {
+            // Do not leave the provider running, e.g. waiting for a request.
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(err);
+        }
+    };
+
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(io::Error::other(format!(
+            "credential provider `{executable}` failed with status `{status}`",
+        )));
+    }
+
+    let CredentialGetResponse { token, cache } = match response {
+        Ok(response) => response,
+        Err(CredentialError::UrlNotSupported | CredentialError::NotFound) => {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("credential provider `{executable}` has no token for {index_url}"),
+            ))
+        }
+        Err(CredentialError::OperationNotSupported) => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("credential provider `{executable}` does not support reading"),
+            ))
+        }
+        Err(CredentialError::Other { message }) => {
+            return Err(io::Error::other(format!(
+                "credential provider `{executable}` failed: {message}",
+            )))
+        }
+    };
+    let token = SecretString::from_boxed_str(token);
+
+    let expiration = match cache {
+        CredentialCacheControl::Never => None,
+        CredentialCacheControl::Session => Some(None),
+        CredentialCacheControl::Expires { expiration } => Some(Some(expiration)),
+    };
+    if let Some(expiration) = expiration {
+        CREDENTIAL_PROCESS_CACHE.lock().unwrap().insert(
+            index_url,
+            CachedToken {
+                token: token.clone(),
+                expiration,
+            },
+        );
+    }
+
+    Ok(token)
+}
+
 pub(crate) fn resolve_registry_auth(
     cargo_config: &CargoConfig,
     cargo_home: &Path,
@@ -252,6 +517,30 @@ pub(crate) fn resolve_registry_auth(
         SupportedRegistryCredentialProvider::CargoTokenFromStdout(provider_args) => {
             resolve_cargo_token_from_stdout(&provider_args, registry_name, registry).ok()?
         }
+        SupportedRegistryCredentialProvider::CredentialProcess(provider_args) => {
+            let registry_name = registry_name.map(CompactString::from);
+            let registry = registry.clone();
+            return Some(RegistryAuth::credential_provider(
+                registry_name.clone(),
+                move || {
+                    resolve_credential_process(&provider_args, registry_name.as_deref(), &registry)
+                },
+            ));
+        }
+        #[cfg(feature = "asymmetric-token")]
+        SupportedRegistryCredentialProvider::CargoPaseto => {
+            let cargo_credentials = Credentials::load_from_home(cargo_home).ok()?;
+            let key = resolve_cargo_paseto(&cargo_credentials, registry_name, registry)?;
+            return Some(RegistryAuth::asymmetric(
+                registry_name.map(CompactString::from),
+                key,
+            ));
+        }
+        #[cfg(not(feature = "asymmetric-token"))]
+        SupportedRegistryCredentialProvider::CargoPaseto => {
+            warn!("cargo:paseto is not supported in this build, it requires the `asymmetric-token` feature");
+            return None;
+        }
     };
 
     RegistryAuth::new(registry_name.map(CompactString::from), token)
@@ -262,7 +551,6 @@ mod tests {
     use std::{env, io::Cursor, sync::Mutex};
 
     use super::*;
-    use once_cell::sync::Lazy;
     use tempfile::tempdir;
 
     static ENV_LOCK: Lazy<Mutex<()>> = Lazy::new(|| Mutex::new(()));
@@ -372,7 +660,7 @@ custom = "cargo:token"
     }
 
     #[test]
-    fn test_registry_provider_alias_non_token_disables_cargo_token() {
+    fn test_registry_provider_alias_enables_credential_process() {
         let _guard = ENV_LOCK.lock().unwrap();
         let config = CargoConfig::load_from_reader(
             Cursor::new(
@@ -389,7 +677,16 @@ custom = ["cargo-credential-example", "--account", "test"]
         )
         .unwrap();
 
-        assert!(resolve_registry_credential_provider(&config, Some("private-registry")).is_none());
+        assert_eq!(
+            resolve_registry_credential_provider(&config, Some("private-registry")),
+            Some(SupportedRegistryCredentialProvider::CredentialProcess(
+                vec![
+                    "cargo-credential-example".into(),
+                    "--account".into(),
+                    "test".into()
+                ]
+            ))
+        );
     }
 
     #[test]
@@ -534,7 +831,7 @@ credential-provider = "cargo:token-from-stdout rustc --print sysroot"
             resolve_registry_auth(&config, tempdir.path(), Some("private-registry"), &registry)
                 .unwrap();
 
-        assert!(!auth.token().is_empty());
+        assert!(!auth.token().unwrap().is_empty());
         assert_eq!(auth.registry_name(), Some("private-registry"));
     }
 
@@ -569,7 +866,7 @@ token = "secret-token"
             resolve_registry_auth(&config, tempdir.path(), Some("private-registry"), &registry)
                 .unwrap();
 
-        assert_eq!(auth.token(), "secret-token");
+        assert_eq!(auth.token(), Some("secret-token"));
         assert_eq!(auth.registry_name(), Some("private-registry"));
     }
 
@@ -598,7 +895,7 @@ credential-provider = "cargo:token"
             resolve_registry_auth(&config, tempdir.path(), Some("private-registry"), &registry)
                 .unwrap();
 
-        assert_eq!(auth.token(), "secret-token");
+        assert_eq!(auth.token(), Some("secret-token"));
         assert_eq!(auth.registry_name(), Some("private-registry"));
 
         env::remove_var("CARGO_REGISTRIES_PRIVATE_REGISTRY_TOKEN");
@@ -668,4 +965,117 @@ credential-provider = "cargo:libsecret"
 
         env::remove_var("CARGO_REGISTRIES_PRIVATE_REGISTRY_TOKEN");
     }
+
+    #[cfg(feature = "asymmetric-token")]
+    #[test]
+    fn test_resolve_registry_auth_uses_secret_key_when_cargo_paseto_is_enabled() {
+        let _guard = ENV_LOCK.lock().unwrap();
+        env::remove_var("CARGO_REGISTRIES_PRIVATE_REGISTRY_SECRET_KEY");
+
+        let config = CargoConfig::load_from_reader(
+            Cursor::new(
+                r#"
+[registries.private-registry]
+index = "sparse+https://registry.example.com/index/"
+credential-provider = "cargo:paseto"
+                "#,
+            ),
+            std::path::Path::new("."),
+        )
+        .unwrap();
+        let tempdir = tempdir().unwrap();
+        let registry: Registry = "sparse+https://registry.example.com/index/"
+            .parse()
+            .unwrap();
+
+        assert!(resolve_registry_auth(
+            &config,
+            tempdir.path(),
+            Some("private-registry"),
+            &registry,
+        )
+        .is_none());
+
+        std::fs::write(
+            tempdir.path().join("credentials.toml"),
+            r#"
+[registries.private-registry]
+secret-key = "k3.secret.fNYVuMvBgOlljt9TDohnaYLblghqaHoQquVZwgR6X12cBFHZLFsaU3q7X3k1Zn36"
+            "#,
+        )
+        .unwrap();
+
+        let auth =
+            resolve_registry_auth(&config, tempdir.path(), Some("private-registry"), &registry)
+                .unwrap();
+
+        assert_eq!(auth.token(), None);
+        assert_eq!(auth.registry_name(), Some("private-registry"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_registry_auth_uses_credential_process() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let _guard = ENV_LOCK.lock().unwrap();
+        let tempdir = tempdir().unwrap();
+        let provider = tempdir.path().join("cargo-credential-test");
+        std::fs::write(
+            &provider,
+            r#"#!/bin/sh
+[ "$1" = "--cargo-plugin" ] || exit 1
+echo '{"v":[1]}'
+read -r request
+case "$request" in
+    *'"kind":"get","operation":"read","args":["--account","test"]'*)
+        echo '{"Ok":{"kind":"get","token":"provider-token","cache":"session","operation_independent":true}}'
+        ;;
+    *)
+        echo '{"Err":{"kind":"operation-not-supported"}}'
+        ;;
+esac
+"#,
+        )
+        .unwrap();
+        std::fs::set_permissions(&provider, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let config = CargoConfig::load_from_reader(
+            Cursor::new(format!(
+                r#"
+[registries.private-registry]
+index = "sparse+https://credential-process.example.com/index/"
+credential-provider = ["{}", "--account", "test"]
+                "#,
+                provider.display()
+            )),
+            std::path::Path::new("."),
+        )
+        .unwrap();
+        let registry: Registry = "sparse+https://credential-process.example.com/index/"
+            .parse()
+            .unwrap();
+
+        // The provider is only run once the registry requires authentication
+        let auth =
+            resolve_registry_auth(&config, tempdir.path(), Some("private-registry"), &registry)
+                .unwrap();
+        assert_eq!(auth.token(), None);
+        assert_eq!(auth.registry_name(), Some("private-registry"));
+
+        let provider_args = [
+            CompactString::from(provider.to_str().unwrap()),
+            "--account".into(),
+            "test".into(),
+        ];
+        let token = resolve_credential_process(&provider_args, Some("private-registry"), &registry)
+            .unwrap();
+        assert_eq!(&token[..], "provider-token");
+
+        // The token is cached for the session
+        std::fs::remove_file(&provider).unwrap();
+        let token = resolve_credential_process(&provider_args, Some("private-registry"), &registry)
+            .unwrap();
+        assert_eq!(&token[..], "provider-token");
+    }
 }
@@ -1,9 +1,12 @@
 use std::{
+    collections::BTreeMap,
     fs::{create_dir_all, File},
     io::{Read, Seek as _, Write as _},
     path::{Path, PathBuf},
 };
 
+use binstalk::registry::{MissingPubtime, ReleaseAge};
+use compact_str::CompactString;
 use fs_lock::FileLock;
 use miette::{miette, IntoDiagnostic, Result, WrapErr};
 use serde::{Deserialize, Serialize};
@@ -20,7 +23,21 @@ pub struct Settings {
     pub track_installs: bool,
     pub continue_on_failure: bool,
     pub targets: Option<Vec<String>>,
+    /// Also try the foreign targets that can be run through an interpreter
+    /// registered in `binfmt_misc`, e.g. `qemu-user`, when detecting targets.
+    pub emulated_targets: bool,
     pub strategies: Vec<StrategyWrapped>,
+    pub keep_backups: usize,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub renames: BTreeMap<CompactString, BTreeMap<CompactString, CompactString>>,
+    pub smoke_test: SmokeTest,
+    pub strict_shared_libs: bool,
+    pub wasi: Wasi,
+    pub policy: Policy,
+    pub advisories: Advisories,
+    pub cache: Cache,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub post_install_hooks: Vec<PostInstallHook>,
     pub telemetry: Telemetry,
 }
 
@@ -32,12 +49,133 @@ impl Default for Settings {
             track_installs: true,
             continue_on_failure: false,
             targets: None,
+            emulated_targets: false,
             strategies: vec![],
+            keep_backups: 1,
+            renames: BTreeMap::new(),
+            smoke_test: SmokeTest::default(),
+            strict_shared_libs: false,
+            wasi: Wasi::default(),
+            policy: Policy::default(),
+            advisories: Advisories::default(),
+            cache: Cache::default(),
+            post_install_hooks: Vec::new(),
             telemetry: Telemetry::default(),
         }
     }
 }
 
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SmokeTest {
+    pub enabled: bool,
+    pub command: Vec<CompactString>,
+    pub check_version: bool,
+    pub timeout_secs: u64,
+}
+
+impl Default for SmokeTest {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            command: vec!["{ bin }".into(), "--version".into()],
+            check_version: true,
+            timeout_secs: 30,
+        }
+    }
+}
+
+/// How to run installed WASI modules.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Wasi {
+    /// Preferred runtime, e.g. `wasmtime`.
+    pub runtime: Option<CompactString>,
+    /// Extra flags passed to the runtime before the module.
+    pub flags: Vec<CompactString>,
+}
+
+/// Policy on the crates to install.
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Policy {
+    /// SPDX licenses allowed, any license not denied is allowed if empty.
+    pub allowed_licenses: Vec<CompactString>,
+    /// SPDX licenses denied, taking precedence over `allowed_licenses`.
+    pub denied_licenses: Vec<CompactString>,
+    /// Ignore versions published less than this long ago, e.g. `7d`,
+    /// unless requested exactly.
+    pub min_release_age: Option<ReleaseAge>,
+    /// Whether versions without publish time in the registry index are
+    /// ignored when `min_release_age` is set.
+    pub missing_pubtime: MissingPubtime,
+}
+
+/// Check of crates against a RustSec advisory database.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Advisories {
+    pub enabled: bool,
+    /// Refuse to install crates affected by advisories.
+    pub deny: bool,
+    /// Path to a local checkout of the advisory database.
+    pub db_path: Option<PathBuf>,
+    /// Git repository to fetch the advisory database from, if `db_path`
+    /// is not set.
+    pub db_url: CompactString,
+}
+
+impl Default for Advisories {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            deny: false,
+            db_path: None,
+            db_url: "https://github.com/rustsec/advisory-db".into(),
+        }
+    }
+}
+
+/// On-disk cache of sparse registry indexes, revalidated with conditional
+/// requests, of git registry indexes, fetched incrementally, and of the
+/// manifests extracted from crates.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Cache {
+    pub enabled: bool,
+    /// Directory of the cache, `binstall/cache` in the cargo home by default.
+    ///
+    /// Sparse indexes are cached in `index/<host>-<hash of the index url>`,
+    /// git indexes are cloned into `git-index/<host>-<hash of the index url>`
+    /// and cloned again once they have more than 100 pack files, and
+    /// manifests are cached in `manifests/<host>-<hash of the index url>`.
+    pub dir: Option<PathBuf>,
+}
+
+impl Default for Cache {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            dir: None,
+        }
+    }
+}
+
+/// Command run after crates are installed or upgraded from pre-built
+/// binaries, receiving the `CrateInfo` of the crate as JSON on stdin and in
+/// `BINSTALL_CRATE_INFO`.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct PostInstallHook {
+    /// Command to run, the first element is the program.
+    pub command: Vec<CompactString>,
+    /// Crates to run the hook for, all crates if empty.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub crates: Vec<CompactString>,
+    /// Fail the installation if the hook fails, instead of only warning.
+    #[serde(default)]
+    pub required: bool,
+}
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Telemetry {
     pub enabled: bool,
@@ -75,6 +213,19 @@ impl Settings {
         if args.continue_on_failure {
             self.continue_on_failure = true;
         }
+        if args.smoke_test {
+            self.smoke_test.enabled = true;
+        }
+        if args.strict_shared_libs {
+            self.strict_shared_libs = true;
+        }
+        if let Some(min_release_age) = args.min_release_age {
+            self.policy.min_release_age = Some(min_release_age);
+        }
+        if args.deny_advisories {
+            self.advisories.enabled = true;
+            self.advisories.deny = true;
+        }
         if let Some(targets) = &args.targets {
             self.targets = Some(targets.clone());
         }
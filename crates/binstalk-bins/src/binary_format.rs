@@ -0,0 +1,663 @@
+//! Minimal parsing of executable headers, used to detect binaries built for
+//! another target than the one they are advertised for.
+
+use std::{
+    fs::File,
+    io::{self, Read, Seek, SeekFrom},
+    path::Path,
+};
+
+use binstalk_types::cargo_toml_binstall::{ArchOr, TargetTriple};
+use compact_str::{format_compact, CompactString};
+use detect_targets::parse_glibc_version;
+use target_lexicon::{Architecture, Environment, OperatingSystem};
+
+/// Number of bytes read from the start of the file, enough for the headers
+/// of all supported formats in practice.
+const HEADER_LEN: usize = 4096;
+
+/// `SHT_DYNAMIC`, section containing the dynamic linking information.
+const SHT_DYNAMIC: u32 = 6;
+
+/// `SHT_GNU_verneed`, section containing the versions of symbols required
+/// from shared libraries.
+const SHT_GNU_VERNEED: u32 = 0x6fff_fffe;
+
+/// Read an unsigned integer of `size` bytes at `off`.
+pub(crate) fn read_uint(buf: &[u8], off: usize, size: usize, le: bool) -> Option<u64> {
+    let bytes = buf.get(off..off.checked_add(size)?)?;
+    let fold = |acc: u64, b: &u8| (acc << 8) | u64::from(*b);
+    Some(if le {
+        bytes.iter().rev().fold(0, fold)
+    } else {
+        bytes.iter().fold(0, fold)
+    })
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Format {
+    Elf,
+    MachO,
+    Pe,
+    Wasm,
+}
+
+impl Format {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Elf => "ELF",
+            Self::MachO => "Mach-O",
+            Self::Pe => "PE",
+            Self::Wasm => "wasm",
+        }
+    }
+
+    fn expected(target: &TargetTriple) -> Self {
+        match (target.arch, target.os) {
+            (ArchOr::Arch(Architecture::Wasm32 | Architecture::Wasm64), _) => Self::Wasm,
+            (ArchOr::Universal, _) => Self::MachO,
+            (
+                _,
+                OperatingSystem::Darwin(_)
+                | OperatingSystem::MacOSX(_)
+                | OperatingSystem::IOS(_)
+                | OperatingSystem::TvOS(_)
+                | OperatingSystem::VisionOS(_)
+                | OperatingSystem::WatchOS(_)
+                | OperatingSystem::XROS(_),
+            ) => Self::MachO,
+            (_, OperatingSystem::Windows | OperatingSystem::Uefi) => Self::Pe,
+            _ => Self::Elf,
+        }
+    }
+}
+
+/// Machine type and bitness of an executable.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Machine {
+    X86,
+    X86_64,
+    Arm,
+    Aarch64,
+    Riscv,
+    Mips,
+    PowerPc,
+    PowerPc64,
+    S390,
+    Sparc64,
+    LoongArch,
+    Other(u32),
+}
+
+impl Machine {
+    fn from_elf(e_machine: u16) -> Self {
+        match e_machine {
+            3 => Self::X86,
+            62 => Self::X86_64,
+            40 => Self::Arm,
+            183 => Self::Aarch64,
+            243 => Self::Riscv,
+            8 => Self::Mips,
+            20 => Self::PowerPc,
+            21 => Self::PowerPc64,
+            22 => Self::S390,
+            43 => Self::Sparc64,
+            258 => Self::LoongArch,
+            other => Self::Other(other.into()),
+        }
+    }
+
+    fn from_mach_o(cputype: u32) -> Self {
+        match cputype {
+            7 => Self::X86,
+            0x0100_0007 => Self::X86_64,
+            12 => Self::Arm,
+            0x0100_000c | 0x0200_000c => Self::Aarch64,
+            18 => Self::PowerPc,
+            0x0100_0012 => Self::PowerPc64,
+            other => Self::Other(other),
+        }
+    }
+
+    fn from_pe(machine: u16) -> Self {
+        match machine {
+            0x14c => Self::X86,
+            0x8664 => Self::X86_64,
+            0x1c0 | 0x1c2 | 0x1c4 => Self::Arm,
+            0xaa64 => Self::Aarch64,
+            0x5032 | 0x5064 => Self::Riscv,
+            other => Self::Other(other.into()),
+        }
+    }
+
+    /// Return the machine type and bitness expected for `target`, or `None`
+    /// if the architecture is not checked.
+    fn expected(target: &TargetTriple) -> Option<(Self, u8)> {
+        let ArchOr::Arch(arch) = target.arch else {
+            return None;
+        };
+
+        Some(match arch {
+            Architecture::X86_32(_) => (Self::X86, 32),
+            Architecture::X86_64 | Architecture::X86_64h => (
+                Self::X86_64,
+                if target.env == Environment::Gnux32 {
+                    32
+                } else {
+                    64
+                },
+            ),
+            Architecture::Arm(_) => (Self::Arm, 32),
+            Architecture::Aarch64(_) => (Self::Aarch64, 64),
+            Architecture::Riscv32(_) => (Self::Riscv, 32),
+            Architecture::Riscv64(_) => (Self::Riscv, 64),
+            Architecture::Mips32(_) => (Self::Mips, 32),
+            Architecture::Mips64(_) => (Self::Mips, 64),
+            Architecture::Powerpc => (Self::PowerPc, 32),
+            Architecture::Powerpc64 | Architecture::Powerpc64le => (Self::PowerPc64, 64),
+            Architecture::S390x => (Self::S390, 64),
+            Architecture::Sparc64 | Architecture::Sparcv9 => (Self::Sparc64, 64),
+            Architecture::LoongArch64 => (Self::LoongArch, 64),
+            _ => return None,
+        })
+    }
+}
+
+/// Operating systems an ELF binary explicitly branded with `EI_OSABI` can
+/// run on.
+///
+/// Most binaries use the generic System V ABI and are not checked.
+fn elf_os_abi(os_abi: u8) -> Option<(&'static str, &'static [OperatingSystem])> {
+    match os_abi {
+        2 => Some(("netbsd", &[OperatingSystem::Netbsd])),
+        3 => Some(("linux", &[OperatingSystem::Linux])),
+        6 => Some((
+            "solaris",
+            &[OperatingSystem::Solaris, OperatingSystem::Illumos],
+        )),
+        9 => Some(("freebsd", &[OperatingSystem::Freebsd])),
+        12 => Some(("openbsd", &[OperatingSystem::Openbsd])),
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+struct Header {
+    format: Format,
+    /// Machine types and bitness of the binary; a Mach-O universal binary
+    /// contains several.
+    machines: Vec<(Machine, u8)>,
+    os_abi: Option<(&'static str, &'static [OperatingSystem])>,
+}
+
+impl Header {
+    /// Return `None` if the format is not recognised, e.g. for scripts.
+    fn parse(buf: &[u8]) -> Option<Self> {
+        let u16_at = |off, le| read_uint(buf, off, 2, le).map(|v| v as u16);
+        let u32_at = |off, le| read_uint(buf, off, 4, le).map(|v| v as u32);
+
+        match buf.get(..4)? {
+            b"\x7fELF" => {
+                let bits = match buf.get(4)? {
+                    1 => 32,
+                    2 => 64,
+                    _ => return None,
+                };
+                let le = *buf.get(5)? == 1;
+                Some(Self {
+                    format: Format::Elf,
+                    machines: vec![(Machine::from_elf(u16_at(18, le)?), bits)],
+                    os_abi: elf_os_abi(*buf.get(7)?),
+                })
+            }
+            b"\0asm" => Some(Self {
+                format: Format::Wasm,
+                machines: Vec::new(),
+                os_abi: None,
+            }),
+            [b'M', b'Z', ..] => {
+                let pe = usize::try_from(u32_at(0x3c, true)?).ok()?;
+                if buf.get(pe..pe + 4)? != b"PE\0\0" {
+                    return None;
+                }
+                let bits = match u16_at(pe + 24, true)? {
+                    0x10b => 32,
+                    0x20b => 64,
+                    _ => return None,
+                };
+                Some(Self {
+                    format: Format::Pe,
+                    machines: vec![(Machine::from_pe(u16_at(pe + 4, true)?), bits)],
+                    os_abi: None,
+                })
+            }
+            _ => {
+                let magic = u32_at(0, false)?;
+                match magic {
+                    0xfeedface | 0xfeedfacf | 0xcefaedfe | 0xcffaedfe => {
+                        let le = matches!(magic, 0xcefaedfe | 0xcffaedfe);
+                        let bits = if matches!(magic, 0xfeedface | 0xcefaedfe) {
+                            32
+                        } else {
+                            64
+                        };
+                        Some(Self {
+                            format: Format::MachO,
+                            machines: vec![(Machine::from_mach_o(u32_at(4, le)?), bits)],
+                            os_abi: None,
+                        })
+                    }
+                    // Universal binary, the header is always big-endian.
+                    0xcafebabe => {
+                        let nfat_arch = usize::try_from(u32_at(4, false)?).ok()?;
+                        let machines = (0..nfat_arch)
+                            .map(|i| {
+                                let machine = Machine::from_mach_o(u32_at(8 + i * 20, false)?);
+                                Some((machine, if machine == Machine::X86 { 32 } else { 64 }))
+                            })
+                            .collect::<Option<Vec<_>>>()?;
+                        Some(Self {
+                            format: Format::MachO,
+                            machines,
+                            os_abi: None,
+                        })
+                    }
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// Return the reason if the binary cannot run on `target`.
+    fn check(&self, target: &TargetTriple) -> Result<(), CompactString> {
+        let format = Format::expected(target);
+        if self.format != format {
+            return Err(format_compact!(
+                "it is a {} binary, expected {}",
+                self.format.name(),
+                format.name()
+            ));
+        }
+
+        if let Some((os_abi, oses)) = self.os_abi {
+            if !oses.contains(&target.os) {
+                return Err(format_compact!(
+                    "it is built for {os_abi}, expected {}",
+                    target.os
+                ));
+            }
+        }
+
+        if let Some((machine, bits)) = Machine::expected(target) {
+            // Bitness is not checked for universal binaries.
+            let matched = if let [(m, b)] = self.machines[..] {
+                m == machine && b == bits
+            } else {
+                self.machines.iter().any(|(m, _)| *m == machine)
+            };
+
+            if !matched {
+                return Err(format_compact!(
+                    "it is built for {}, expected {:?} ({bits}-bit)",
+                    self.machines
+                        .iter()
+                        .map(|(m, b)| format!("{m:?} ({b}-bit)"))
+                        .collect::<Vec<_>>()
+                        .join(", "),
+                    machine,
+                ));
+            }
+        } else if target.arch == ArchOr::Universal && self.machines.len() < 2 {
+            return Err("it is not a universal binary".into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Check that the executable at `path` can run on `target`.
+///
+/// Files with an unrecognised format, e.g. scripts, are accepted.
+///
+/// Returns `Ok(Err(reason))` if it cannot.
+pub(crate) fn check(path: &Path, target: &TargetTriple) -> io::Result<Result<(), CompactString>> {
+    let mut buf = Vec::with_capacity(HEADER_LEN);
+    File::open(path)?
+        .take(HEADER_LEN as u64)
+        .read_to_end(&mut buf)?;
+
+    Ok(Header::parse(&buf).map_or(Ok(()), |header| header.check(target)))
+}
+
+/// Section headers of an ELF file, with the section contents read on demand.
+struct Elf {
+    file: File,
+    is_64: bool,
+    le: bool,
+    sections: Vec<u8>,
+    shentsize: u64,
+    shnum: u64,
+}
+
+#[derive(Copy, Clone)]
+struct Section {
+    sh_type: u32,
+    offset: u64,
+    size: u64,
+    link: u64,
+}
+
+impl Elf {
+    /// Return `None` if `path` is not an ELF file.
+    fn open(path: &Path) -> io::Result<Option<Self>> {
+        let mut elf = Self {
+            file: File::open(path)?,
+            is_64: false,
+            le: false,
+            sections: Vec::new(),
+            shentsize: 0,
+            shnum: 0,
+        };
+
+        let header = elf.read_at(0, 64)?;
+        if header.get(..4) != Some(b"\x7fELF") {
+            return Ok(None);
+        }
+        elf.is_64 = header.get(4) == Some(&2);
+        elf.le = header.get(5) == Some(&1);
+
+        // Offsets of fields in the ELF header differ between 32-bit and
+        // 64-bit ELF.
+        let (shoff, shentsize, shnum) = if elf.is_64 {
+            (0x28, 0x3a, 0x3c)
+        } else {
+            (0x20, 0x2e, 0x30)
+        };
+        let (Some(shoff), Some(shentsize), Some(shnum)) = (
+            read_uint(&header, shoff, elf.word(), elf.le),
+            read_uint(&header, shentsize, 2, elf.le),
+            read_uint(&header, shnum, 2, elf.le),
+        ) else {
+            return Ok(None);
+        };
+
+        elf.sections = elf.read_at(shoff, shentsize * shnum)?;
+        elf.shentsize = shentsize;
+        elf.shnum = shnum;
+
+        Ok(Some(elf))
+    }
+
+    fn word(&self) -> usize {
+        if self.is_64 {
+            8
+        } else {
+            4
+        }
+    }
+
+    fn read_at(&mut self, off: u64, len: u64) -> io::Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        self.file.seek(SeekFrom::Start(off))?;
+        (&mut self.file).take(len).read_to_end(&mut buf)?;
+        Ok(buf)
+    }
+
+    fn section(&self, index: u64) -> Option<Section> {
+        let base = usize::try_from(index * self.shentsize).ok()?;
+        let (offset, size, link) = if self.is_64 {
+            (0x18, 0x20, 0x28)
+        } else {
+            (0x10, 0x14, 0x18)
+        };
+        let read = |off, size| read_uint(&self.sections, base + off, size, self.le);
+
+        Some(Section {
+            sh_type: read(4, 4)? as u32,
+            offset: read(offset, self.word())?,
+            size: read(size, self.word())?,
+            link: read(link, 4)?,
+        })
+    }
+
+    /// Return the content of the first section of type `sh_type` and of
+    /// the string table it links to.
+    fn read_section_with_strtab(&mut self, sh_type: u32) -> io::Result<Option<(Vec<u8>, Vec<u8>)>> {
+        let Some(section) = (0..self.shnum)
+            .filter_map(|index| self.section(index))
+            .find(|section| section.sh_type == sh_type)
+        else {
+            return Ok(None);
+        };
+        let Some(strtab) = self.section(section.link) else {
+            return Ok(None);
+        };
+
+        Ok(Some((
+            self.read_at(section.offset, section.size)?,
+            self.read_at(strtab.offset, strtab.size)?,
+        )))
+    }
+}
+
+/// Read the nul-terminated string at `off` in the string table.
+pub(crate) fn c_str(strtab: &[u8], off: u64) -> Option<&str> {
+    let bytes = strtab.get(usize::try_from(off).ok()?..)?;
+    let end = bytes.iter().position(|b| *b == 0)?;
+    std::str::from_utf8(&bytes[..end]).ok()
+}
+
+/// Return the highest `GLIBC_x.y` symbol version required by the ELF
+/// executable at `path` as `(x, y)`, or `None` if it requires none or
+/// is not an ELF executable.
+pub(crate) fn required_glibc_version(path: &Path) -> io::Result<Option<(u32, u32)>> {
+    let Some(mut elf) = Elf::open(path)? else {
+        return Ok(None);
+    };
+    let Some((verneed, strtab)) = elf.read_section_with_strtab(SHT_GNU_VERNEED)? else {
+        return Ok(None);
+    };
+
+    Ok(max_glibc_version(&verneed, &strtab, elf.le))
+}
+
+/// Shared libraries required by an ELF executable.
+#[derive(Debug, Default)]
+pub(crate) struct DynamicInfo {
+    /// `DT_NEEDED` entries.
+    pub(crate) needed: Vec<String>,
+    /// Directories in `DT_RUNPATH`, or in `DT_RPATH` if there is no `DT_RUNPATH`.
+    pub(crate) runpath: Vec<String>,
+}
+
+/// Return `None` if `path` is not a dynamically linked ELF executable.
+pub(crate) fn dynamic_info(path: &Path) -> io::Result<Option<DynamicInfo>> {
+    let Some(mut elf) = Elf::open(path)? else {
+        return Ok(None);
+    };
+    let Some((dynamic, strtab)) = elf.read_section_with_strtab(SHT_DYNAMIC)? else {
+        return Ok(None);
+    };
+
+    Ok(Some(parse_dynamic(&dynamic, &strtab, elf.word(), elf.le)))
+}
+
+fn parse_dynamic(dynamic: &[u8], strtab: &[u8], word: usize, le: bool) -> DynamicInfo {
+    const DT_NULL: u64 = 0;
+    const DT_NEEDED: u64 = 1;
+    const DT_RPATH: u64 = 15;
+    const DT_RUNPATH: u64 = 29;
+
+    let mut info = DynamicInfo::default();
+    let mut rpath = Vec::new();
+
+    for entry in dynamic.chunks_exact(word * 2) {
+        let (Some(tag), Some(val)) = (
+            read_uint(entry, 0, word, le),
+            read_uint(entry, word, word, le),
+        ) else {
+            break;
+        };
+        let dst = match tag {
+            DT_NULL => break,
+            DT_NEEDED => &mut info.needed,
+            DT_RPATH => &mut rpath,
+            DT_RUNPATH => &mut info.runpath,
+            _ => continue,
+        };
+        let Some(s) = c_str(strtab, val) else {
+            continue;
+        };
+
+        if tag == DT_NEEDED {
+            dst.push(s.to_owned());
+        } else {
+            dst.extend(
+                s.split(':')
+                    .filter(|dir| !dir.is_empty())
+                    .map(str::to_owned),
+            );
+        }
+    }
+
+    if info.runpath.is_empty() {
+        info.runpath = rpath;
+    }
+
+    info
+}
+
+/// Walk the `Elf_Verneed` entries and their `Elf_Vernaux` entries, which
+/// have the same layout in 32-bit and 64-bit ELF.
+fn max_glibc_version(verneed: &[u8], strtab: &[u8], le: bool) -> Option<(u32, u32)> {
+    let mut max = None;
+    let mut off = 0usize;
+    loop {
+        let cnt = read_uint(verneed, off + 2, 2, le)?;
+        let mut aux =
+            off.checked_add(usize::try_from(read_uint(verneed, off + 8, 4, le)?).ok()?)?;
+
+        for _ in 0..cnt {
+            let version = c_str(strtab, read_uint(verneed, aux + 8, 4, le)?)
+                .and_then(|name| name.strip_prefix("GLIBC_"))
+                .and_then(parse_glibc_version);
+            max = max.max(version);
+
+            let next = read_uint(verneed, aux + 12, 4, le)?;
+            if next == 0 {
+                break;
+            }
+            aux = aux.checked_add(usize::try_from(next).ok()?)?;
+        }
+
+        let next = read_uint(verneed, off + 12, 4, le)?;
+        if next == 0 {
+            break max;
+        }
+        off = off.checked_add(usize::try_from(next).ok()?)?;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn elf(bits: u8, machine: u16, os_abi: u8) -> Vec<u8> {
+        let mut buf = vec![0; 64];
+        buf[..4].copy_from_slice(b"\x7fELF");
+        buf[4] = if bits == 32 { 1 } else { 2 };
+        buf[5] = 1;
+        buf[7] = os_abi;
+        buf[18..20].copy_from_slice(&machine.to_le_bytes());
+        buf
+    }
+
+    fn pe(machine: u16, magic: u16) -> Vec<u8> {
+        let mut buf = vec![0; 0x100];
+        buf[..2].copy_from_slice(b"MZ");
+        buf[0x3c..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        buf[0x80..0x84].copy_from_slice(b"PE\0\0");
+        buf[0x84..0x86].copy_from_slice(&machine.to_le_bytes());
+        buf[0x98..0x9a].copy_from_slice(&magic.to_le_bytes());
+        buf
+    }
+
+    fn mach_o_fat(cputypes: &[u32]) -> Vec<u8> {
+        let mut buf = 0xcafebabe_u32.to_be_bytes().to_vec();
+        buf.extend((cputypes.len() as u32).to_be_bytes());
+        for cputype in cputypes {
+            buf.extend(cputype.to_be_bytes());
+            buf.extend([0; 16]);
+        }
+        buf
+    }
+
+    fn check(buf: &[u8], target: &str) -> Result<(), CompactString> {
+        Header::parse(buf).unwrap().check(&target.parse().unwrap())
+    }
+
+    #[test]
+    fn elf_binaries() {
+        let x86_64 = elf(64, 62, 0);
+        check(&x86_64, "x86_64-unknown-linux-gnu").unwrap();
+        check(&x86_64, "x86_64-unknown-linux-musl").unwrap();
+        check(&x86_64, "aarch64-unknown-linux-gnu").unwrap_err();
+        check(&x86_64, "x86_64-pc-windows-msvc").unwrap_err();
+        check(&x86_64, "i686-unknown-linux-gnu").unwrap_err();
+
+        check(&elf(32, 40, 0), "armv7-unknown-linux-gnueabihf").unwrap();
+        check(&elf(64, 183, 3), "aarch64-unknown-linux-musl").unwrap();
+        check(&elf(64, 62, 9), "x86_64-unknown-freebsd").unwrap();
+        check(&elf(64, 62, 9), "x86_64-unknown-linux-gnu").unwrap_err();
+    }
+
+    #[test]
+    fn pe_binaries() {
+        check(&pe(0x8664, 0x20b), "x86_64-pc-windows-msvc").unwrap();
+        check(&pe(0x14c, 0x10b), "i686-pc-windows-gnu").unwrap();
+        check(&pe(0xaa64, 0x20b), "x86_64-pc-windows-msvc").unwrap_err();
+        check(&pe(0x8664, 0x20b), "x86_64-unknown-linux-gnu").unwrap_err();
+    }
+
+    #[test]
+    fn mach_o_binaries() {
+        let universal = mach_o_fat(&[0x0100_0007, 0x0100_000c]);
+        check(&universal, "universal-apple-darwin").unwrap();
+        check(&universal, "aarch64-apple-darwin").unwrap();
+
+        let mut arm64 = 0xfeedfacf_u32.to_le_bytes().to_vec();
+        arm64.extend(0x0100_000c_u32.to_le_bytes());
+        check(&arm64, "aarch64-apple-darwin").unwrap();
+        check(&arm64, "x86_64-apple-darwin").unwrap_err();
+        check(&arm64, "universal-apple-darwin").unwrap_err();
+    }
+
+    #[test]
+    fn unknown_format() {
+        assert!(Header::parse(b"#!/bin/sh\necho hello\n").is_none());
+        check(b"\0asm\x01\0\0\0", "wasm32-wasip1").unwrap();
+    }
+
+    #[test]
+    fn glibc_version() {
+        let strtab = b"\0libc.so.6\0GLIBC_2.2.5\0GLIBC_2.34\0GLIBC_PRIVATE\0";
+
+        let mut verneed = Vec::new();
+        // Elf_Verneed: version, cnt, file, aux, next
+        verneed.extend(1u16.to_le_bytes());
+        verneed.extend(3u16.to_le_bytes());
+        verneed.extend(1u32.to_le_bytes());
+        verneed.extend(16u32.to_le_bytes());
+        verneed.extend(0u32.to_le_bytes());
+        // Elf_Vernaux: hash, flags, other, name, next
+        for (name, next) in [(11u32, 16u32), (23, 16), (34, 0)] {
+            verneed.extend(0u32.to_le_bytes());
+            verneed.extend(0u16.to_le_bytes());
+            verneed.extend(0u16.to_le_bytes());
+            verneed.extend(name.to_le_bytes());
+            verneed.extend(next.to_le_bytes());
+        }
+
+        assert_eq!(max_glibc_version(&verneed, strtab, true), Some((2, 34)));
+    }
+}
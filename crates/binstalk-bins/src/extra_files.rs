@@ -0,0 +1,206 @@
+//! Files other than binaries in packages, e.g. man pages and shell
+//! completions, installed into `share` under the install root.
+
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use atomic_file_install::{atomic_install, atomic_install_noclobber};
+use binstalk_types::cargo_toml_binstall::{self, ExtraFileKind, PkgFmt};
+use compact_str::{format_compact, CompactString};
+use leon::Template;
+use normalize_path::NormalizePath;
+use tracing::debug;
+
+use crate::{binary_ext, is_valid_path, Context, Data, Error, Transaction};
+
+pub struct ExtraFile {
+    pub kind: ExtraFileKind,
+    pub source: PathBuf,
+    pub archive_source_path: PathBuf,
+    /// Destination relative to the install root, using `/` as separator.
+    pub rel_dest: CompactString,
+    pub dest: PathBuf,
+}
+
+impl ExtraFile {
+    /// Return `None` if the package is a single binary, which cannot
+    /// contain any extra files.
+    ///
+    /// * `root` - the install root, files are installed into `root/share`.
+    pub fn new(
+        data: &Data<'_>,
+        extra_file: &cargo_toml_binstall::ExtraFile,
+        root: &Path,
+    ) -> Result<Option<Self>, Error> {
+        if data.meta.pkg_fmt == Some(PkgFmt::Bin) {
+            return Ok(None);
+        }
+
+        let ctx = Context {
+            name: data.name,
+            repo: data.repo,
+            target: data.target,
+            version: data.version,
+            bin: data.name,
+            binary_ext: binary_ext(data.target),
+
+            target_related_info: data.target_related_info,
+        };
+
+        let path = Template::parse(&extra_file.source)?.render(&ctx)?;
+        let path_normalized = Path::new(&path).normalize();
+
+        if !is_valid_path(&path_normalized) {
+            return Err(Error::InvalidSourceFilePath(path_normalized.into()));
+        }
+        let Some(file_name) = path_normalized.file_name().and_then(|name| name.to_str()) else {
+            return Err(Error::EmptySourceFilePath);
+        };
+
+        let rel_dest = share_path(extra_file.kind, data.name, file_name);
+
+        Ok(Some(Self {
+            kind: extra_file.kind,
+            source: data.bin_path.join(&path_normalized),
+            dest: root.join(&*rel_dest),
+            rel_dest,
+            archive_source_path: path_normalized,
+        }))
+    }
+
+    /// Return `Ok` if the source exists, otherwise `Err`.
+    pub fn check_source_exists(
+        &self,
+        has_file: &mut dyn FnMut(&Path) -> bool,
+    ) -> Result<(), Error> {
+        if has_file(&self.archive_source_path) {
+            Ok(())
+        } else {
+            Err(Error::ExtraFileNotFound((&*self.source).into()))
+        }
+    }
+
+    pub fn preview(&self) -> impl fmt::Display + '_ {
+        struct Preview<'a>(&'a Path, &'a Path);
+
+        impl fmt::Display for Preview<'_> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{} => {}", self.0.display(), self.1.display())
+            }
+        }
+
+        Preview(&self.archive_source_path, &self.dest)
+    }
+
+    fn pre_install(&self, transaction: &mut Transaction) -> Result<(), Error> {
+        if !self.source.try_exists()? {
+            return Err(Error::ExtraFileNotFound((&*self.source).into()));
+        }
+        if let Some(parent) = self.dest.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        transaction.prepare(&self.dest)?;
+
+        Ok(())
+    }
+
+    /// `transaction` records the destination so that it can be restored
+    /// if the installation fails later.
+    pub fn install(&self, transaction: &mut Transaction) -> Result<(), Error> {
+        self.pre_install(transaction)?;
+
+        debug!(
+            "Atomically install file from '{}' to '{}'",
+            self.source.display(),
+            self.dest.display()
+        );
+        atomic_install(&self.source, &self.dest)?;
+
+        Ok(())
+    }
+
+    pub fn install_noclobber(&self, transaction: &mut Transaction) -> Result<(), Error> {
+        self.pre_install(transaction)?;
+
+        debug!(
+            "Installing file from '{}' to '{}' only if dst not exists",
+            self.source.display(),
+            self.dest.display()
+        );
+        atomic_install_noclobber(&self.source, &self.dest)?;
+
+        Ok(())
+    }
+}
+
+/// Return where to install a file of `kind` named `file_name` provided by
+/// crate `name`, relative to the install root.
+fn share_path(kind: ExtraFileKind, name: &str, file_name: &str) -> CompactString {
+    match kind {
+        ExtraFileKind::Man => {
+            // Use the section in the file name, e.g. `foo.1` or `foo.1.gz`
+            let section = file_name
+                .strip_suffix(".gz")
+                .unwrap_or(file_name)
+                .rsplit_once('.')
+                .and_then(|(_, ext)| ext.chars().next())
+                .filter(char::is_ascii_digit)
+                .unwrap_or('1');
+            format_compact!("share/man/man{section}/{file_name}")
+        }
+        ExtraFileKind::BashCompletion => {
+            // bash-completion loads completions named after the command
+            let file_name = file_name.strip_suffix(".bash").unwrap_or(file_name);
+            format_compact!("share/bash-completion/completions/{file_name}")
+        }
+        ExtraFileKind::ZshCompletion => format_compact!("share/zsh/site-functions/{file_name}"),
+        ExtraFileKind::FishCompletion => {
+            format_compact!("share/fish/vendor_completions.d/{file_name}")
+        }
+        ExtraFileKind::License => format_compact!("share/licenses/{name}/{file_name}"),
+        ExtraFileKind::Doc => format_compact!("share/doc/{name}/{file_name}"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_share_path() {
+        assert_eq!(
+            share_path(ExtraFileKind::Man, "foo", "foo.1"),
+            "share/man/man1/foo.1"
+        );
+        assert_eq!(
+            share_path(ExtraFileKind::Man, "foo", "foo-config.5.gz"),
+            "share/man/man5/foo-config.5.gz"
+        );
+        assert_eq!(
+            share_path(ExtraFileKind::Man, "foo", "foo"),
+            "share/man/man1/foo"
+        );
+        assert_eq!(
+            share_path(ExtraFileKind::BashCompletion, "foo", "foo.bash"),
+            "share/bash-completion/completions/foo"
+        );
+        assert_eq!(
+            share_path(ExtraFileKind::ZshCompletion, "foo", "_foo"),
+            "share/zsh/site-functions/_foo"
+        );
+        assert_eq!(
+            share_path(ExtraFileKind::FishCompletion, "foo", "foo.fish"),
+            "share/fish/vendor_completions.d/foo.fish"
+        );
+        assert_eq!(
+            share_path(ExtraFileKind::License, "foo", "LICENSE-MIT"),
+            "share/licenses/foo/LICENSE-MIT"
+        );
+        assert_eq!(
+            share_path(ExtraFileKind::Doc, "foo", "README.md"),
+            "share/doc/foo/README.md"
+        );
+    }
+}
@@ -1,13 +1,14 @@
 use std::{
     borrow::Cow,
-    fmt, io,
+    fmt, fs, io,
     path::{self, Component, Path, PathBuf},
 };
 
+pub use atomic_file_install::Transaction;
 use atomic_file_install::{
     atomic_install, atomic_install_noclobber, atomic_symlink_file, atomic_symlink_file_noclobber,
 };
-use binstalk_types::cargo_toml_binstall::{PkgFmt, PkgMeta};
+use binstalk_types::cargo_toml_binstall::{PkgFmt, PkgMeta, TargetTriple};
 use compact_str::{format_compact, CompactString};
 use leon::Template;
 use miette::Diagnostic;
@@ -15,6 +16,14 @@ use normalize_path::NormalizePath;
 use thiserror::Error as ThisError;
 use tracing::debug;
 
+mod binary_format;
+mod extra_files;
+mod shared_libs;
+mod wasi;
+
+pub use extra_files::ExtraFile;
+pub use wasi::{is_wasi_target, WasiLauncher};
+
 #[derive(Debug, ThisError, Diagnostic)]
 pub enum Error {
     /// bin-dir configuration provided generates source path outside
@@ -32,9 +41,51 @@ pub enum Error {
     #[error("bin file {} not found", .0.display())]
     BinFileNotFound(Box<Path>),
 
+    /// Extra file, e.g. a man page, is not found.
+    #[error("extra file {} not found", .0.display())]
+    ExtraFileNotFound(Box<Path>),
+
+    /// Bin file is an executable for another target.
+    #[error("bin file {} cannot run on target {target}: {reason}", .path.display())]
+    IncompatibleBinary {
+        path: Box<Path>,
+        target: CompactString,
+        reason: CompactString,
+    },
+
+    /// Bin file requires a newer glibc than the one on the host.
+    #[error(
+        "bin file {} requires glibc {}.{}, but the host has glibc {}.{}",
+        .path.display(), .required.0, .required.1, .host_version.0, .host_version.1
+    )]
+    GlibcTooOld {
+        path: Box<Path>,
+        required: (u32, u32),
+        host_version: (u32, u32),
+    },
+
+    /// Bin file is dynamically linked against shared libraries that are
+    /// not found on the host.
+    #[error(
+        "bin file {} needs shared libraries that are not found on the host: {}",
+        .path.display(), .libs.join(", ")
+    )]
+    MissingSharedLibs {
+        path: Box<Path>,
+        libs: Vec<CompactString>,
+    },
+
+    /// The name to install the binary under is not a valid file name.
+    #[error("invalid name to install bin file as: {0}")]
+    InvalidInstallName(CompactString),
+
     #[error(transparent)]
     Io(#[from] io::Error),
 
+    #[error("Failed to parse template: {0}")]
+    #[diagnostic(transparent)]
+    TemplateParse(#[from] leon::ParseError),
+
     #[error("Failed to render template: {0}")]
     #[diagnostic(transparent)]
     TemplateRender(#[from] leon::RenderError),
@@ -93,8 +144,39 @@ pub fn infer_bin_dir_template(
         .unwrap_or(default_bin_dir_template)
 }
 
+/// Return the value of `{ binary-ext }` in `bin-dir` for `target`.
+pub fn binary_ext(target: &str) -> &'static str {
+    if target.contains("windows") {
+        ".exe"
+    } else {
+        ""
+    }
+}
+
+/// Return the extension of installed binaries of `target`, including the
+/// leading dot.
+///
+/// Unlike [`binary_ext`], this is `.wasm` for WASI targets, so that the
+/// modules can be told apart from their launchers.
+pub fn installed_ext(target: &str) -> &'static str {
+    if is_wasi_target(target) {
+        ".wasm"
+    } else {
+        binary_ext(target)
+    }
+}
+
+/// Return the file name `{bin}-v{version}{installed_ext}` used for binaries
+/// installed side by side with other versions.
+pub fn versioned_file_name(bin: &str, version: &str, installed_ext: &str) -> String {
+    format!("{bin}-v{version}{installed_ext}")
+}
+
 pub struct BinFile {
     pub base_name: CompactString,
+    /// Name of the installed binary, which differs from `base_name`
+    /// if the binary is renamed.
+    pub install_name: CompactString,
     pub source: PathBuf,
     pub archive_source_path: PathBuf,
     pub dest: PathBuf,
@@ -103,17 +185,17 @@ pub struct BinFile {
 
 impl BinFile {
     /// * `tt` - must have a template with name "bin_dir"
+    /// * `install_name` - if provided, install the binary under this name
+    ///   instead of `base_name`, without binary extension.
     pub fn new(
         data: &Data<'_>,
         base_name: &str,
+        install_name: Option<&str>,
         tt: &Template<'_>,
         no_symlinks: bool,
     ) -> Result<Self, Error> {
-        let binary_ext = if data.target.contains("windows") {
-            ".exe"
-        } else {
-            ""
-        };
+        let binary_ext = binary_ext(data.target);
+        let installed_ext = installed_ext(data.target);
 
         let ctx = Context {
             name: data.name,
@@ -149,29 +231,36 @@ impl BinFile {
             (data.bin_path.join(&path_normalized), path_normalized)
         };
 
-        // Destination at install dir + base-name{.extension}
-        let mut dest = data.install_path.join(ctx.bin);
-        if !binary_ext.is_empty() {
-            let binary_ext = binary_ext.strip_prefix('.').unwrap();
+        let install_name = install_name.unwrap_or(base_name);
+        if Path::new(install_name).file_name() != Some(install_name.as_ref()) {
+            return Err(Error::InvalidInstallName(install_name.into()));
+        }
+
+        // Destination at install dir + install-name{.extension}
+        let mut dest = data.install_path.join(install_name);
+        if !installed_ext.is_empty() {
+            let installed_ext = installed_ext.strip_prefix('.').unwrap();
 
             // PathBuf::set_extension returns false if Path::file_name
             // is None, but we know that the file name must be Some,
             // thus we assert! the return value here.
-            assert!(dest.set_extension(binary_ext));
+            assert!(dest.set_extension(installed_ext));
         }
 
         let (dest, link) = if no_symlinks {
             (dest, None)
         } else {
-            // Destination path is the install dir + base-name-version{.extension}
-            let dest_file_path_with_ver = format!("{}-v{}{}", ctx.bin, ctx.version, ctx.binary_ext);
+            // Destination path is the install dir + install-name-version{.extension}
+            let dest_file_path_with_ver =
+                versioned_file_name(install_name, ctx.version, installed_ext);
             let dest_with_ver = data.install_path.join(dest_file_path_with_ver);
 
             (dest_with_ver, Some(dest))
         };
 
         Ok(Self {
-            base_name: format_compact!("{base_name}{binary_ext}"),
+            base_name: format_compact!("{base_name}{installed_ext}"),
+            install_name: format_compact!("{install_name}{installed_ext}"),
             source,
             archive_source_path,
             dest,
@@ -217,6 +306,66 @@ impl BinFile {
         }
     }
 
+    /// Return `Ok` if the source is an executable that can run on `target`,
+    /// based on its format, machine type, bitness and OS ABI.
+    ///
+    /// Sources in an unrecognised format, e.g. scripts, are accepted.
+    ///
+    /// This is a blocking function.
+    pub fn check_binary_format(&self, target: &str) -> Result<(), Error> {
+        let Ok(target_triple) = target.parse::<TargetTriple>() else {
+            debug!(
+                "Cannot parse target {target}, skip checking format of {}",
+                self.source.display()
+            );
+            return Ok(());
+        };
+
+        binary_format::check(&self.source, &target_triple)?.map_err(|reason| {
+            Error::IncompatibleBinary {
+                path: (&*self.source).into(),
+                target: target.into(),
+                reason,
+            }
+        })
+    }
+
+    /// Return `Ok` if the source does not require a newer glibc than
+    /// `host_version`, given as `(major, minor)`.
+    ///
+    /// This is a blocking function.
+    pub fn check_glibc_version(&self, host_version: (u32, u32)) -> Result<(), Error> {
+        match binary_format::required_glibc_version(&self.source)? {
+            Some(required) if required > host_version => Err(Error::GlibcTooOld {
+                path: (&*self.source).into(),
+                required,
+                host_version,
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Return `Ok` if all shared libraries the source is dynamically linked
+    /// against are found on the host.
+    ///
+    /// This is a blocking function.
+    pub fn check_shared_libs(&self) -> Result<(), Error> {
+        let Some(info) = binary_format::dynamic_info(&self.source)? else {
+            return Ok(());
+        };
+
+        let origin = self.dest.parent().unwrap_or(Path::new("."));
+        let libs = shared_libs::find_missing(&info, origin);
+        if libs.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::MissingSharedLibs {
+                path: (&*self.source).into(),
+                libs,
+            })
+        }
+    }
+
     fn pre_install_bin(&self) -> Result<(), Error> {
         if !self.source.try_exists()? {
             return Err(Error::BinFileNotFound((&*self.source).into()));
@@ -231,7 +380,9 @@ impl BinFile {
         Ok(())
     }
 
-    pub fn install_bin(&self) -> Result<(), Error> {
+    /// `transaction` records the destination so that it can be restored
+    /// if the installation fails later.
+    pub fn install_bin(&self, transaction: &mut Transaction) -> Result<(), Error> {
         self.pre_install_bin()?;
 
         debug!(
@@ -240,12 +391,13 @@ impl BinFile {
             self.dest.display()
         );
 
+        transaction.prepare(&self.dest)?;
         atomic_install(&self.source, &self.dest)?;
 
         Ok(())
     }
 
-    pub fn install_bin_noclobber(&self) -> Result<(), Error> {
+    pub fn install_bin_noclobber(&self, transaction: &mut Transaction) -> Result<(), Error> {
         self.pre_install_bin()?;
 
         debug!(
@@ -254,12 +406,13 @@ impl BinFile {
             self.dest.display()
         );
 
+        transaction.prepare(&self.dest)?;
         atomic_install_noclobber(&self.source, &self.dest)?;
 
         Ok(())
     }
 
-    pub fn install_link(&self) -> Result<(), Error> {
+    pub fn install_link(&self, transaction: &mut Transaction) -> Result<(), Error> {
         if let Some(link) = &self.link {
             let dest = self.link_dest();
             debug!(
@@ -267,13 +420,14 @@ impl BinFile {
                 link.display(),
                 dest.display()
             );
+            transaction.prepare(link)?;
             atomic_symlink_file(dest, link)?;
         }
 
         Ok(())
     }
 
-    pub fn install_link_noclobber(&self) -> Result<(), Error> {
+    pub fn install_link_noclobber(&self, transaction: &mut Transaction) -> Result<(), Error> {
         if let Some(link) = &self.link {
             let dest = self.link_dest();
             debug!(
@@ -281,12 +435,94 @@ impl BinFile {
                 link.display(),
                 dest.display()
             );
+            transaction.prepare(link)?;
             atomic_symlink_file_noclobber(dest, link)?;
         }
 
         Ok(())
     }
 
+    /// Return the path of the launcher of a WASI module, which is its
+    /// installed name without the `.wasm` extension, or `None` if this is
+    /// not a WASI module.
+    pub fn wasi_launcher_path(&self) -> Option<PathBuf> {
+        let module = self.link.as_ref().unwrap_or(&self.dest);
+        if module.extension()? != "wasm" {
+            return None;
+        }
+
+        let mut path = module.with_extension("");
+        if cfg!(windows) {
+            path.set_extension("cmd");
+        }
+        Some(path)
+    }
+
+    /// Install the launcher of a WASI module, so that it can be run like
+    /// a native binary. Do nothing if this is not a WASI module.
+    pub fn install_wasi_launcher(
+        &self,
+        launcher: &WasiLauncher,
+        transaction: &mut Transaction,
+    ) -> Result<(), Error> {
+        self.install_wasi_launcher_inner(launcher, transaction, false)
+    }
+
+    pub fn install_wasi_launcher_noclobber(
+        &self,
+        launcher: &WasiLauncher,
+        transaction: &mut Transaction,
+    ) -> Result<(), Error> {
+        self.install_wasi_launcher_inner(launcher, transaction, true)
+    }
+
+    fn install_wasi_launcher_inner(
+        &self,
+        launcher: &WasiLauncher,
+        transaction: &mut Transaction,
+        noclobber: bool,
+    ) -> Result<(), Error> {
+        let Some(path) = self.wasi_launcher_path() else {
+            return Ok(());
+        };
+        let module = self.link.as_ref().unwrap_or(&self.dest);
+
+        debug!(
+            "Create launcher '{}' for WASI module '{}' run by {}",
+            path.display(),
+            module.display(),
+            launcher.runtime_name(),
+        );
+        transaction.prepare(&path)?;
+
+        let Some(script) = launcher.script(module) else {
+            let dest = if cfg!(target_family = "unix") {
+                Path::new(module.file_name().unwrap())
+            } else {
+                module
+            };
+            if noclobber {
+                atomic_symlink_file_noclobber(dest, &path)?;
+            } else {
+                atomic_symlink_file(dest, &path)?;
+            }
+            return Ok(());
+        };
+
+        let source = self.source.with_extension("launcher");
+        fs::write(&source, script)?;
+        #[cfg(unix)]
+        fs::set_permissions(&source, std::os::unix::fs::PermissionsExt::from_mode(0o755))?;
+
+        if noclobber {
+            atomic_install_noclobber(&source, &path)?;
+        } else {
+            atomic_install(&source, &path)?;
+        }
+
+        Ok(())
+    }
+
     fn link_dest(&self) -> &Path {
         if cfg!(target_family = "unix") {
             Path::new(self.dest.file_name().unwrap())
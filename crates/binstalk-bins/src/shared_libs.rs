@@ -0,0 +1,159 @@
+//! Lookup of shared libraries on the host, following the search path of the
+//! dynamic loader closely enough to detect libraries that are missing.
+
+use std::{
+    collections::BTreeSet,
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+use compact_str::CompactString;
+
+use crate::binary_format::{c_str, read_uint, DynamicInfo};
+
+const LD_SO_CACHE: &str = "/etc/ld.so.cache";
+const LD_SO_CACHE_MAGIC: &[u8] = b"glibc-ld.so.cache1.1";
+/// Header: magic, nlibs, len_strings, flags, padding, extension_offset, unused.
+const LD_SO_CACHE_HEADER_LEN: usize = 48;
+/// Entry: flags, key, value, osversion, hwcap.
+const LD_SO_CACHE_ENTRY_LEN: usize = 24;
+
+/// Directories searched by both glibc and musl after the configured ones.
+const DEFAULT_DIRS: &[&str] = &["/lib", "/lib64", "/usr/lib", "/usr/lib64", "/usr/local/lib"];
+
+/// Return the libraries in `info.needed` that cannot be found on the host.
+///
+///  * `origin` - directory the binary is installed to, substituted for
+///    `$ORIGIN` in the runpath.
+///
+/// This is a blocking function.
+pub(crate) fn find_missing(info: &DynamicInfo, origin: &Path) -> Vec<CompactString> {
+    let origin = origin.to_string_lossy();
+
+    let mut dirs: Vec<PathBuf> = info
+        .runpath
+        .iter()
+        .map(|dir| {
+            dir.replace("${ORIGIN}", &origin)
+                .replace("$ORIGIN", &origin)
+                .into()
+        })
+        .collect();
+    if let Some(paths) = env::var_os("LD_LIBRARY_PATH") {
+        dirs.extend(env::split_paths(&paths));
+    }
+    dirs.extend(musl_path_dirs());
+    dirs.extend(DEFAULT_DIRS.iter().map(PathBuf::from));
+
+    let cache = fs::read(LD_SO_CACHE).unwrap_or_default();
+    let cached = cached_libs(&cache);
+
+    info.needed
+        .iter()
+        .filter(|lib| {
+            let found = if lib.contains('/') {
+                Path::new(lib).exists()
+            } else {
+                cached.contains(lib.as_str()) || dirs.iter().any(|dir| dir.join(lib).exists())
+            };
+            !found
+        })
+        .map(CompactString::from)
+        .collect()
+}
+
+/// Directories configured in `/etc/ld-musl-$ARCH.path`.
+fn musl_path_dirs() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir("/etc") else {
+        return Vec::new();
+    };
+
+    entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let name = path.file_name()?.to_str()?;
+            (name.starts_with("ld-musl-") && name.ends_with(".path"))
+                .then(|| fs::read_to_string(&path).ok())
+                .flatten()
+        })
+        .flat_map(|content| {
+            content
+                .split([':', '\n'])
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+/// Return the names of the libraries in glibc's `ld.so.cache`.
+///
+/// The cache is written in the host's byte order, either in the new format
+/// alone, or appended to the entries of the old format.
+fn cached_libs(cache: &[u8]) -> BTreeSet<&str> {
+    let le = cfg!(target_endian = "little");
+
+    let Some(start) = cache
+        .windows(LD_SO_CACHE_MAGIC.len())
+        .position(|window| window == LD_SO_CACHE_MAGIC)
+    else {
+        return BTreeSet::new();
+    };
+    // String offsets are relative to the start of the new format.
+    let cache = &cache[start..];
+
+    let Some(nlibs) = read_uint(cache, LD_SO_CACHE_MAGIC.len(), 4, le) else {
+        return BTreeSet::new();
+    };
+
+    (0..nlibs as usize)
+        .map_while(|i| {
+            read_uint(
+                cache,
+                LD_SO_CACHE_HEADER_LEN + i * LD_SO_CACHE_ENTRY_LEN + 4,
+                4,
+                le,
+            )
+        })
+        .filter_map(|key| c_str(cache, key))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_cached_libs() {
+        let mut cache = b"ld.so-1.7.0\0".to_vec();
+        cache.extend(0u32.to_ne_bytes());
+
+        let start = cache.len();
+        let strings = LD_SO_CACHE_HEADER_LEN + 2 * LD_SO_CACHE_ENTRY_LEN;
+        cache.extend(LD_SO_CACHE_MAGIC);
+        cache.extend(2u32.to_ne_bytes());
+        cache.resize(start + LD_SO_CACHE_HEADER_LEN, 0);
+        for key in [strings, strings + 12] {
+            cache.extend(0x303u32.to_ne_bytes());
+            cache.extend((key as u32).to_ne_bytes());
+            cache.extend([0; 16]);
+        }
+        cache.extend(b"libssl.so.3\0libz.so.1\0");
+
+        assert_eq!(
+            cached_libs(&cache).into_iter().collect::<Vec<_>>(),
+            ["libssl.so.3", "libz.so.1"]
+        );
+    }
+
+    #[test]
+    fn test_find_missing() {
+        let info = DynamicInfo {
+            needed: vec!["libc.so.6".into(), "libdoes-not-exist.so.42".into()],
+            runpath: vec!["$ORIGIN/../lib".into()],
+        };
+
+        let missing = find_missing(&info, Path::new("/nonexistent/bin"));
+        assert!(missing.contains(&CompactString::from("libdoes-not-exist.so.42")));
+    }
+}
@@ -0,0 +1,110 @@
+//! Launchers for WASI modules, so that they can be run like native binaries.
+
+use std::path::Path;
+
+use compact_str::CompactString;
+
+/// Return true if binaries of `target` are WASI modules.
+pub fn is_wasi_target(target: &str) -> bool {
+    target.starts_with("wasm32-wasi")
+}
+
+/// How to run an installed WASI module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WasiLauncher {
+    /// The module can be executed directly, e.g. through an interpreter
+    /// registered in `binfmt_misc`, so the launcher is a symlink to it.
+    Direct,
+    /// The module is run by a WASI runtime through a launcher script,
+    /// e.g. `wasmtime run`.
+    Runtime {
+        program: CompactString,
+        args: Vec<CompactString>,
+    },
+}
+
+impl WasiLauncher {
+    /// Arguments to pass to known runtimes before the module.
+    pub fn default_runtime_args(program: &str) -> &'static [&'static str] {
+        let name = Path::new(program)
+            .file_stem()
+            .and_then(|name| name.to_str())
+            .unwrap_or(program);
+        match name {
+            "wasmtime" | "wasmer" => &["run"],
+            _ => &[],
+        }
+    }
+
+    /// Name of the runtime recorded in the manifests.
+    pub fn runtime_name(&self) -> &str {
+        match self {
+            Self::Direct => "direct",
+            Self::Runtime { program, .. } => program,
+        }
+    }
+
+    /// Render the launcher script running `module` with the runtime,
+    /// or `None` if the launcher is a symlink.
+    pub(crate) fn script(&self, module: &Path) -> Option<String> {
+        let Self::Runtime { program, args } = self else {
+            return None;
+        };
+        let module = module.to_string_lossy();
+        let words = [program]
+            .into_iter()
+            .chain(args)
+            .map(|word| quote(word))
+            .chain([quote(&module)])
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        Some(if cfg!(windows) {
+            format!("@{words} %*\r\n")
+        } else {
+            format!("#!/bin/sh\nexec {words} \"$@\"\n")
+        })
+    }
+}
+
+fn quote(word: &str) -> String {
+    if cfg!(windows) {
+        format!("\"{word}\"")
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use super::*;
+
+    #[test]
+    fn script() {
+        assert_eq!(
+            WasiLauncher::Direct.script(Path::new("/bin/foo.wasm")),
+            None
+        );
+
+        let launcher = WasiLauncher::Runtime {
+            program: "wasmtime".into(),
+            args: vec!["run".into(), "--dir=.".into()],
+        };
+        assert_eq!(
+            launcher
+                .script(Path::new("/home/o'neil/bin/foo.wasm"))
+                .unwrap(),
+            "#!/bin/sh\nexec 'wasmtime' 'run' '--dir=.' '/home/o'\\''neil/bin/foo.wasm' \"$@\"\n"
+        );
+    }
+
+    #[test]
+    fn default_runtime_args() {
+        assert_eq!(WasiLauncher::default_runtime_args("wasmtime"), ["run"]);
+        assert_eq!(
+            WasiLauncher::default_runtime_args("/usr/bin/wasmer"),
+            ["run"]
+        );
+        assert!(WasiLauncher::default_runtime_args("wasmedge").is_empty());
+    }
+}
@@ -2,7 +2,11 @@ use std::{fmt, io, path::Path};
 
 use binstalk_types::cargo_toml_binstall::PkgFmtDecomposed;
 use bytes::Bytes;
-use futures_util::{stream::FusedStream, Stream, StreamExt};
+use futures_util::{
+    future::Either,
+    stream::{self, FusedStream},
+    Stream, StreamExt,
+};
 use thiserror::Error as ThisError;
 use tracing::{debug, error, instrument};
 
@@ -82,13 +86,30 @@ impl DataVerifier for () {
 enum DownloadContent {
     ToIssue { client: Client, url: Url },
     Response(Response),
+    Bytes(Bytes),
 }
 
 impl DownloadContent {
-    async fn into_response(self) -> Result<Response, DownloadError> {
+    async fn into_bytes_stream(
+        self,
+    ) -> Result<impl Stream<Item = Result<Bytes, RemoteError>> + Send + Sync + Unpin, DownloadError>
+    {
+        Ok(match self {
+            DownloadContent::ToIssue { client, url } => {
+                Either::Right(client.get(url).send(true).await?.bytes_stream())
+            }
+            DownloadContent::Response(response) => Either::Right(response.bytes_stream()),
+            DownloadContent::Bytes(bytes) => Either::Left(stream::iter([Ok(bytes)])),
+        })
+    }
+
+    async fn into_bytes(self) -> Result<Bytes, DownloadError> {
         Ok(match self {
-            DownloadContent::ToIssue { client, url } => client.get(url).send(true).await?,
-            DownloadContent::Response(response) => response,
+            DownloadContent::ToIssue { client, url } => {
+                client.get(url).send(true).await?.bytes().await?
+            }
+            DownloadContent::Response(response) => response.bytes().await?,
+            DownloadContent::Bytes(bytes) => bytes,
         })
     }
 }
@@ -118,6 +139,15 @@ impl Download<'static> {
             data_verifier: None,
         }
     }
+
+    /// Process `bytes` already in memory, e.g. read from a local file,
+    /// instead of downloading them.
+    pub fn from_bytes(bytes: Bytes) -> Self {
+        Self {
+            content: DownloadContent::Bytes(bytes),
+            data_verifier: None,
+        }
+    }
 }
 
 impl<'a> Download<'a> {
@@ -156,11 +186,9 @@ impl<'a> Download<'a> {
         DownloadError,
     > {
         let mut data_verifier = self.data_verifier;
-        Ok(self
-            .content
-            .into_response()
-            .await?
-            .bytes_stream()
+        let stream = self.content.into_bytes_stream().await?;
+
+        Ok(stream
             .map(move |res| {
                 let bytes = res?;
 
@@ -270,7 +298,7 @@ impl Download<'_> {
 
     #[instrument(skip(self))]
     pub async fn into_bytes(self) -> Result<Bytes, DownloadError> {
-        let bytes = self.content.into_response().await?.bytes().await?;
+        let bytes = self.content.into_bytes().await?;
         if let Some(verifier) = self.data_verifier {
             verifier.update(&bytes);
         }
@@ -289,6 +317,31 @@ mod test {
     };
     use tempfile::tempdir;
 
+    #[tokio::test]
+    async fn test_from_bytes_into_bytes() {
+        #[derive(Default)]
+        struct Len(usize);
+
+        impl DataVerifier for Len {
+            fn update(&mut self, data: &Bytes) {
+                self.0 += data.len();
+            }
+            fn validate(&mut self) -> bool {
+                true
+            }
+        }
+
+        let mut verifier = Len::default();
+        let bytes = Download::from_bytes(Bytes::from_static(b"binstall"))
+            .with_data_verifier(&mut verifier)
+            .into_bytes()
+            .await
+            .unwrap();
+
+        assert_eq!(bytes, b"binstall"[..]);
+        assert_eq!(verifier.0, bytes.len());
+    }
+
     #[tokio::test]
     async fn test_and_extract() {
         let client = crate::remote::Client::new(
@@ -510,6 +510,8 @@ impl<'c> Context<'c> {
             archive_suffix,
             binary_ext: if target.contains("windows") {
                 ".exe"
+            } else if target.starts_with("wasm32-wasi") {
+                ".wasm"
             } else {
                 ""
             },
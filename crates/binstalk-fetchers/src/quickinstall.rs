@@ -279,6 +279,8 @@ by rust officially."#,
         let mut meta = self.target_data.meta.clone();
         meta.pkg_fmt = Some(self.pkg_fmt());
         meta.bin_dir = Some("{ bin }{ binary-ext }".to_string());
+        // Packages of QuickInstall only contain the binaries.
+        meta.extra_files = Vec::new();
         meta
     }
 
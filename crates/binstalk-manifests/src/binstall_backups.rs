@@ -0,0 +1,233 @@
+//! Binstall's backups of previously installed binaries.
+//!
+//! Before a crate is upgraded or reinstalled, Binstall copies its binaries
+//! together with the [`CrateInfo`] describing them into
+//! `$CARGO_ROOT/binstall/backups/<crate>/<seq>/`, so that the previous
+//! installation can be restored with `cargo binstall --rollback <crate>`.
+//!
+//! `<seq>` is a monotonically increasing number, the backup with the largest
+//! one is the most recent backup.
+//!
+//! Callers must hold the lock on the binstall manifest while manipulating
+//! backups, which [`Manifests`](crate::crates_manifests::Manifests) takes
+//! care of.
+
+use std::{
+    fs,
+    io::{self, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use miette::Diagnostic;
+use thiserror::Error;
+
+use crate::crate_info::CrateInfo;
+
+const CRATE_INFO_FILENAME: &str = "crate-info.json";
+
+#[derive(Debug, Diagnostic, Error)]
+#[non_exhaustive]
+pub enum Error {
+    #[error("I/O Error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("Failed to parse json: {0}")]
+    SerdeJsonParse(#[from] serde_json::Error),
+}
+
+#[derive(Clone, Debug)]
+pub struct Backups {
+    dir: PathBuf,
+}
+
+impl Backups {
+    /// * `binstall_dir` - `$CARGO_ROOT/binstall`
+    pub fn new(binstall_dir: &Path) -> Self {
+        Self {
+            dir: binstall_dir.join("backups"),
+        }
+    }
+
+    fn crate_dir(&self, name: &str) -> PathBuf {
+        self.dir.join(name)
+    }
+
+    /// Return the sequence numbers of all backups of crate `name`, sorted.
+    fn list(&self, name: &str) -> Result<Vec<u64>, Error> {
+        let entries = match fs::read_dir(self.crate_dir(name)) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err.into()),
+        };
+
+        let mut seqs = Vec::new();
+        for entry in entries {
+            if let Some(seq) = entry?.file_name().to_str().and_then(|s| s.parse().ok()) {
+                seqs.push(seq);
+            }
+        }
+        seqs.sort_unstable();
+
+        Ok(seqs)
+    }
+
+    /// Copy binaries listed in `crate_info` from `install_path` into a new
+    /// backup and return it.
+    ///
+    /// Binaries that no longer exist in `install_path` are skipped.
+    /// Outdated backups are not removed, call [`Backups::prune`] once the
+    /// crate is upgraded, or [`Backup::remove`] if it fails.
+    ///
+    /// This is a blocking function.
+    pub fn create(&self, crate_info: &CrateInfo, install_path: &Path) -> Result<Backup, Error> {
+        let seq = self.list(&crate_info.name)?.last().map_or(0, |seq| seq + 1);
+        let dir = self.crate_dir(&crate_info.name).join(seq.to_string());
+        fs::create_dir_all(&dir)?;
+
+        for bin in &crate_info.bins {
+            let src = install_path.join(bin);
+            match fs::copy(&src, dir.join(bin)) {
+                Ok(_) => (),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => (),
+                Err(err) => return Err(err.into()),
+            }
+        }
+
+        let mut writer = BufWriter::new(fs::File::create(dir.join(CRATE_INFO_FILENAME))?);
+        serde_json::to_writer(&mut writer, crate_info)?;
+        writer.flush()?;
+
+        Ok(Backup {
+            dir,
+            crate_info: crate_info.clone(),
+        })
+    }
+
+    /// Remove the oldest backups of crate `name` so that at most `keep` remains.
+    ///
+    /// This is a blocking function.
+    pub fn prune(&self, name: &str, keep: usize) -> Result<(), Error> {
+        let seqs = self.list(name)?;
+        let outdated = seqs.len().saturating_sub(keep);
+
+        for seq in &seqs[..outdated] {
+            fs::remove_dir_all(self.crate_dir(name).join(seq.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Return the most recent backup of crate `name`, if any.
+    ///
+    /// This is a blocking function.
+    pub fn latest(&self, name: &str) -> Result<Option<Backup>, Error> {
+        let Some(seq) = self.list(name)?.pop() else {
+            return Ok(None);
+        };
+
+        let dir = self.crate_dir(name).join(seq.to_string());
+        let reader = BufReader::new(fs::File::open(dir.join(CRATE_INFO_FILENAME))?);
+        let crate_info = serde_json::from_reader(reader)?;
+
+        Ok(Some(Backup { dir, crate_info }))
+    }
+}
+
+#[derive(Debug)]
+pub struct Backup {
+    dir: PathBuf,
+    pub crate_info: CrateInfo,
+}
+
+impl Backup {
+    /// Path to the backed up binary `bin`, which might not exist if the
+    /// binary was missing when the backup was created.
+    pub fn bin_path(&self, bin: &str) -> PathBuf {
+        self.dir.join(bin)
+    }
+
+    /// Remove the backup from disk.
+    ///
+    /// This is a blocking function.
+    pub fn remove(self) -> Result<(), Error> {
+        fs::remove_dir_all(self.dir).map_err(Error::from)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::crate_info::CrateSource;
+
+    use detect_targets::TARGET;
+    use semver::Version;
+    use tempfile::TempDir;
+
+    fn crate_info(version: Version) -> CrateInfo {
+        CrateInfo {
+            name: "a".into(),
+            version_req: "*".into(),
+            current_version: version,
+            source: CrateSource::cratesio_registry(),
+            target: TARGET.into(),
+            bins: vec!["1".into(), "2".into()],
+            installed_versions: Vec::new(),
+            bin_renames: Default::default(),
+            wasi_runtime: None,
+            license: None,
+            yanked: false,
+            extra_files: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn create_and_prune() {
+        let tempdir = TempDir::new().unwrap();
+        let install_path = tempdir.path().join("bin");
+        fs::create_dir(&install_path).unwrap();
+        // Binary "2" is deliberately missing
+        fs::write(install_path.join("1"), b"v0.1.0").unwrap();
+
+        let backups = Backups::new(tempdir.path());
+        assert!(backups.latest("a").unwrap().is_none());
+
+        backups
+            .create(&crate_info(Version::new(0, 1, 0)), &install_path)
+            .unwrap();
+
+        fs::write(install_path.join("1"), b"v0.2.0").unwrap();
+        backups
+            .create(&crate_info(Version::new(0, 2, 0)), &install_path)
+            .unwrap();
+
+        fs::write(install_path.join("1"), b"v0.3.0").unwrap();
+        let staged = backups
+            .create(&crate_info(Version::new(0, 3, 0)), &install_path)
+            .unwrap();
+        assert_eq!(backups.list("a").unwrap(), [0, 1, 2]);
+
+        // A failed upgrade removes its backup
+        staged.remove().unwrap();
+        assert_eq!(backups.list("a").unwrap(), [0, 1]);
+
+        let staged = backups
+            .create(&crate_info(Version::new(0, 3, 0)), &install_path)
+            .unwrap();
+        assert_eq!(staged.crate_info.current_version, Version::new(0, 3, 0));
+        backups.prune("a", 2).unwrap();
+
+        assert_eq!(backups.list("a").unwrap(), [1, 2]);
+
+        let backup = backups.latest("a").unwrap().unwrap();
+        assert_eq!(backup.crate_info.current_version, Version::new(0, 3, 0));
+        assert_eq!(fs::read(backup.bin_path("1")).unwrap(), b"v0.3.0");
+        assert!(!backup.bin_path("2").exists());
+
+        backup.remove().unwrap();
+        let backup = backups.latest("a").unwrap().unwrap();
+        assert_eq!(backup.crate_info.current_version, Version::new(0, 2, 0));
+
+        backups.prune("a", 0).unwrap();
+        assert!(backups.latest("a").unwrap().is_none());
+    }
+}
@@ -276,6 +276,12 @@ mod test {
                 source: CrateSource::cratesio_registry(),
                 target: target.clone(),
                 bins: vec!["1".into(), "2".into()],
+                installed_versions: Vec::new(),
+                bin_renames: Default::default(),
+                wasi_runtime: None,
+                license: None,
+                yanked: false,
+                extra_files: Vec::new(),
             },
             CrateInfo {
                 name: "b".into(),
@@ -284,6 +290,12 @@ mod test {
                 source: CrateSource::cratesio_registry(),
                 target: target.clone(),
                 bins: vec!["1".into(), "2".into()],
+                installed_versions: Vec::new(),
+                bin_renames: Default::default(),
+                wasi_runtime: None,
+                license: None,
+                yanked: false,
+                extra_files: Vec::new(),
             },
             CrateInfo {
                 name: "a".into(),
@@ -292,6 +304,12 @@ mod test {
                 source: CrateSource::cratesio_registry(),
                 target: target.clone(),
                 bins: vec!["1".into()],
+                installed_versions: Vec::new(),
+                bin_renames: Default::default(),
+                wasi_runtime: None,
+                license: None,
+                yanked: false,
+                extra_files: Vec::new(),
             },
         ];
 
@@ -322,6 +340,12 @@ mod test {
             source: CrateSource::cratesio_registry(),
             target,
             bins: vec!["1".into(), "2".into()],
+            installed_versions: Vec::new(),
+            bin_renames: Default::default(),
+            wasi_runtime: None,
+            license: None,
+            yanked: false,
+            extra_files: Vec::new(),
         };
         append_to_path(path, [new_metadata.clone()]).unwrap();
         metadata_set.insert(new_metadata);
@@ -67,6 +67,46 @@ pub struct Registry {
     pub credential_provider: Option<CredentialProvider>,
 }
 
+/// An entry of `[source]`, used by cargo for source replacement.
+#[derive(Debug, Deserialize, Merge)]
+pub struct Source {
+    /// Name of the source or registry replacing this source.
+    #[serde(rename = "replace-with")]
+    #[merge(strategy = merge::option::overwrite_none)]
+    pub replace_with: Option<CompactString>,
+    /// Index url of the registry of this source.
+    #[merge(strategy = merge::option::overwrite_none)]
+    pub registry: Option<CompactString>,
+    /// Path to a local registry, with an index and `.crate` files.
+    #[serde(rename = "local-registry")]
+    #[merge(strategy = merge::option::overwrite_none)]
+    pub local_registry: Option<PathBuf>,
+    /// Path to a directory of unpacked crates, e.g. created by `cargo vendor`.
+    #[merge(strategy = merge::option::overwrite_none)]
+    pub directory: Option<PathBuf>,
+}
+
+/// Registry replacing a source according to cargo's `[source]` config.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SourceReplacement<'a> {
+    /// Name of the replacement in `[source]` or `[registries]`.
+    pub name: &'a str,
+    pub kind: SourceReplacementKind<'a>,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SourceReplacementKind<'a> {
+    /// Registry in `[registries]` with its index url, which can be passed to
+    /// `cargo install --registry`.
+    Registry(&'a str),
+    /// `registry` source in `[source]` with its index url.
+    Index(&'a str),
+    /// `local-registry` source in `[source]`.
+    LocalRegistry(&'a Path),
+    /// `directory` source in `[source]`.
+    Directory(&'a Path),
+}
+
 type GlobalCredentialProviders = Option<VecDeque<CompactString>>;
 fn merge_global_credential_providers(
     left: &mut GlobalCredentialProviders,
@@ -179,6 +219,9 @@ pub struct Config {
     #[merge(strategy = merge::option::recurse)]
     pub registry: Option<DefaultRegistry>,
     #[serde(default)]
+    #[merge(strategy = merge_btreemap_recursive)]
+    pub source: BTreeMap<CompactString, Source>,
+    #[serde(default)]
     #[merge(skip)]
     pub include: Vec<IncludedConfig>,
     #[serde(default, rename = "credential-alias")]
@@ -277,6 +320,11 @@ impl Config {
                 }
             }
 
+            for source in config.source.values_mut() {
+                join_if_relative(source.local_registry.as_mut(), dir);
+                join_if_relative(source.directory.as_mut(), dir);
+            }
+
             for included_config in &mut config.include {
                 join_if_relative(Some(included_config.path_mut()), dir);
             }
@@ -344,6 +392,105 @@ impl Config {
             Some(registry)
         }
     }
+
+    /// Return the name of the source in `[source]` whose registry is `index`.
+    ///
+    /// `crates-io` is returned for the crates.io index since cargo has it built-in.
+    pub fn get_source_name_by_index(&self, index: &str) -> Option<&str> {
+        fn normalize(index: &str) -> &str {
+            index.trim_end_matches('/')
+        }
+
+        if CRATES_IO_INDEXES
+            .iter()
+            .any(|crates_io_index| normalize(crates_io_index) == normalize(index))
+        {
+            return Some(CRATES_IO_SOURCE);
+        }
+
+        self.source
+            .iter()
+            .find(|(_, source)| source.registry.as_deref().map(normalize) == Some(normalize(index)))
+            .map(|(name, _)| name.as_str())
+    }
+
+    /// Follow the `replace-with` chain of the source `name` in `[source]`.
+    ///
+    /// Return `None` if the source is not replaced.
+    pub fn get_source_replacement(
+        &self,
+        name: &str,
+    ) -> Result<Option<SourceReplacement<'_>>, SourceReplacementError> {
+        let mut visited = vec![name];
+
+        let mut replace_with = match self
+            .source
+            .get(name)
+            .and_then(|source| source.replace_with.as_deref())
+        {
+            Some(replace_with) => replace_with,
+            None => return Ok(None),
+        };
+
+        loop {
+            if visited.contains(&replace_with) {
+                return Err(SourceReplacementError::Cycle(name.into()));
+            }
+            visited.push(replace_with);
+
+            if let Some(source) = self.source.get(replace_with) {
+                if let Some(next) = source.replace_with.as_deref() {
+                    replace_with = next;
+                    continue;
+                }
+
+                let kind = if let Some(index) = source.registry.as_deref() {
+                    SourceReplacementKind::Index(index)
+                } else if let Some(path) = source.local_registry.as_deref() {
+                    SourceReplacementKind::LocalRegistry(path)
+                } else if let Some(path) = source.directory.as_deref() {
+                    SourceReplacementKind::Directory(path)
+                } else {
+                    return Err(SourceReplacementError::Unsupported(replace_with.into()));
+                };
+
+                break Ok(Some(SourceReplacement {
+                    name: replace_with,
+                    kind,
+                }));
+            }
+
+            let index = self
+                .get_registry_index(replace_with)
+                .ok_or_else(|| SourceReplacementError::NotFound(replace_with.into()))?;
+
+            break Ok(Some(SourceReplacement {
+                name: replace_with,
+                kind: SourceReplacementKind::Registry(index),
+            }));
+        }
+    }
+}
+
+/// Name of the crates.io source in cargo's `[source]`.
+pub const CRATES_IO_SOURCE: &str = "crates-io";
+
+const CRATES_IO_INDEXES: &[&str] = &[
+    "https://github.com/rust-lang/crates.io-index",
+    "sparse+https://index.crates.io/",
+];
+
+#[derive(Debug, Diagnostic, Error)]
+#[non_exhaustive]
+pub enum SourceReplacementError {
+    #[error("Source `{0}` is replaced in a cycle in `[source]` of .cargo/config.toml")]
+    Cycle(CompactString),
+
+    #[error("Replacement source `{0}` is neither in `[source]` nor in `[registries]` of .cargo/config.toml")]
+    NotFound(CompactString),
+
+    #[error("Replacement source `{0}` is not supported, only `registry`, `local-registry` and `directory` sources are")]
+    Unsupported(CompactString),
 }
 
 #[derive(Debug, Diagnostic, Error)]
@@ -501,6 +648,8 @@ custom = ["cargo-credential-example", "--account", "test"]
             include: Vec::new(),
             // Same strategy as env
             credential_alias: BTreeMap::new(),
+            // Same strategy as registries
+            source: BTreeMap::new(),
 
             env: BTreeMap::from([
                 (CompactString::new("1"), Env::Value(CompactString::new("1"))),
@@ -535,6 +684,7 @@ custom = ["cargo-credential-example", "--account", "test"]
             http: None,
             include: Vec::new(),
             credential_alias: BTreeMap::new(),
+            source: BTreeMap::new(),
 
             env: BTreeMap::from([
                 (
@@ -616,4 +766,96 @@ custom = ["cargo-credential-example", "--account", "test"]
                 .collect::<Vec<_>>(),
         );
     }
+
+    const SOURCE_REPLACEMENT_CONFIG: &str = r#"
+[source.crates-io]
+replace-with = "mirror"
+
+[source.mirror]
+replace-with = "corp"
+
+[registries.corp]
+index = "sparse+https://corp.example.com/index/"
+
+[source.git-mirror]
+registry = "https://git.example.com/index"
+
+[source.other]
+registry = "https://other.example.com/index"
+replace-with = "git-mirror"
+
+[source.vendored]
+registry = "https://vendored.example.com/index"
+replace-with = "vendor"
+
+[source.vendor]
+directory = "vendor"
+
+[source.offline]
+registry = "https://offline.example.com/index"
+replace-with = "local"
+
+[source.local]
+local-registry = "registry"
+
+[source.a]
+replace-with = "b"
+
+[source.b]
+replace-with = "a"
+    "#;
+
+    #[test]
+    fn test_source_replacement() {
+        let config =
+            Config::load_from_reader(Cursor::new(&SOURCE_REPLACEMENT_CONFIG), Path::new("root"))
+                .unwrap();
+
+        assert_eq!(
+            config.get_source_replacement("crates-io").unwrap(),
+            Some(SourceReplacement {
+                name: "corp",
+                kind: SourceReplacementKind::Registry("sparse+https://corp.example.com/index/"),
+            })
+        );
+        assert_eq!(
+            config.get_source_replacement("other").unwrap(),
+            Some(SourceReplacement {
+                name: "git-mirror",
+                kind: SourceReplacementKind::Index("https://git.example.com/index"),
+            })
+        );
+        assert_eq!(
+            config.get_source_replacement("vendored").unwrap(),
+            Some(SourceReplacement {
+                name: "vendor",
+                kind: SourceReplacementKind::Directory(&Path::new("root").join("vendor")),
+            })
+        );
+        assert_eq!(
+            config.get_source_replacement("offline").unwrap(),
+            Some(SourceReplacement {
+                name: "local",
+                kind: SourceReplacementKind::LocalRegistry(&Path::new("root").join("registry")),
+            })
+        );
+        assert_eq!(config.get_source_replacement("git-mirror").unwrap(), None);
+        assert!(matches!(
+            config.get_source_replacement("a"),
+            Err(SourceReplacementError::Cycle(_))
+        ));
+
+        assert_eq!(
+            config.get_source_name_by_index("sparse+https://index.crates.io"),
+            Some("crates-io")
+        );
+        assert_eq!(
+            config.get_source_name_by_index("https://other.example.com/index/"),
+            Some("other")
+        );
+        assert_eq!(
+            config.get_source_name_by_index("https://unknown.example.com/index"),
+            None
+        );
+    }
 }
@@ -237,6 +237,12 @@ mod tests {
                 source: CrateSource::cratesio_registry(),
                 target: TARGET.into(),
                 bins: vec!["cargo-binstall".into()],
+                installed_versions: Vec::new(),
+                bin_renames: Default::default(),
+                wasi_runtime: None,
+                license: None,
+                yanked: false,
+                extra_files: Vec::new(),
             }],
         )
         .unwrap();
@@ -263,6 +269,12 @@ mod tests {
                 source: CrateSource::cratesio_registry(),
                 target: TARGET.into(),
                 bins: vec!["cargo-binstall".into()],
+                installed_versions: Vec::new(),
+                bin_renames: Default::default(),
+                wasi_runtime: None,
+                license: None,
+                yanked: false,
+                extra_files: Vec::new(),
             }],
         )
         .unwrap();
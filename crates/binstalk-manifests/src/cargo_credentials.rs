@@ -1,7 +1,8 @@
 //! Cargo's credentials file.
 //!
-//! Cargo stores plaintext registry tokens in `$CARGO_HOME/credentials.toml`
-//! or the legacy `$CARGO_HOME/credentials` path.
+//! Cargo stores plaintext registry tokens and secret keys of asymmetric
+//! tokens in `$CARGO_HOME/credentials.toml` or the legacy
+//! `$CARGO_HOME/credentials` path.
 
 use std::{fs::File, io, path::Path};
 
@@ -13,9 +14,14 @@ use serde::{Deserialize, Deserializer};
 use thiserror::Error;
 
 #[derive(Clone, Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
 pub struct RegistryCredential {
     #[serde(default, deserialize_with = "deserialize_secret_string")]
     pub token: Option<SecretString>,
+    /// PASERK secret key used by `cargo:paseto`.
+    #[serde(default, deserialize_with = "deserialize_secret_string")]
+    pub secret_key: Option<SecretString>,
+    pub secret_key_subject: Option<CompactString>,
 }
 
 #[derive(Clone, Debug, Default, Deserialize)]
@@ -63,6 +69,18 @@ impl Credentials {
     pub fn get_registry_token(&self, name: &str) -> Option<&SecretString> {
         self.registries.as_ref()?.get(name)?.token.as_ref()
     }
+
+    pub fn get_registry_secret_key(&self, name: &str) -> Option<&SecretString> {
+        self.registries.as_ref()?.get(name)?.secret_key.as_ref()
+    }
+
+    pub fn get_registry_secret_key_subject(&self, name: &str) -> Option<&str> {
+        self.registries
+            .as_ref()?
+            .get(name)?
+            .secret_key_subject
+            .as_deref()
+    }
 }
 
 fn deserialize_secret_string<'de, D>(deserializer: D) -> Result<Option<SecretString>, D::Error>
@@ -111,6 +129,10 @@ token = "crates-io-token"
 
 [registries.private-registry]
 token = "private-token"
+
+[registries.paseto-registry]
+secret-key = "k3.secret.key"
+secret-key-subject = "subject"
         "#;
 
         let credentials = Credentials::load_from_reader(Cursor::new(CREDENTIALS)).unwrap();
@@ -121,6 +143,16 @@ token = "private-token"
                 .map(|token| &token[..]),
             Some("private-token")
         );
+        assert_eq!(
+            credentials
+                .get_registry_secret_key("paseto-registry")
+                .map(|key| &key[..]),
+            Some("k3.secret.key")
+        );
+        assert_eq!(
+            credentials.get_registry_secret_key_subject("paseto-registry"),
+            Some("subject")
+        );
     }
 
     #[test]
@@ -153,6 +185,7 @@ token = "private-token"
     fn test_registry_credential_debug_redacts_token() {
         let credential = RegistryCredential {
             token: Some(SecretString::from_boxed_str("secret-token".into())),
+            ..Default::default()
         };
 
         let debug = format!("{credential:?}");
@@ -2,7 +2,7 @@ use std::{
     collections::BTreeMap,
     fs,
     io::{self, Seek},
-    path::Path,
+    path::{Component, Path, PathBuf},
 };
 
 use fs_lock::FileLock;
@@ -10,6 +10,7 @@ use miette::Diagnostic;
 use thiserror::Error as ThisError;
 
 use crate::{
+    binstall_backups::{Backup, Backups, Error as BinstallBackupsError},
     binstall_crates_v1::{Error as BinstallCratesV1Error, Records as BinstallCratesV1Records},
     cargo_crates_v1::{CratesToml, CratesTomlParseError},
     crate_info::CrateInfo,
@@ -24,10 +25,17 @@ pub enum ManifestsError {
     #[diagnostic(transparent)]
     BinstallCratesV1(#[from] BinstallCratesV1Error),
 
+    #[error("failed to manipulate binstall backups: {0}")]
+    #[diagnostic(transparent)]
+    BinstallBackups(#[from] BinstallBackupsError),
+
     #[error("failed to parse cargo v1 manifest: {0}")]
     #[diagnostic(transparent)]
     CargoManifestV1(#[from] CratesTomlParseError),
 
+    #[error("invalid extra file path {0}: it must be relative to the install root")]
+    InvalidExtraFilePath(CompactString),
+
     #[error("I/O error: {0}")]
     Io(#[from] io::Error),
 }
@@ -36,6 +44,8 @@ pub struct Manifests {
     binstall: BinstallCratesV1Records,
     cargo_crates_v1: FileLock,
     installed_crates: BTreeMap<CompactString, Version>,
+    backups: Backups,
+    cargo_root: PathBuf,
 }
 
 impl Manifests {
@@ -62,6 +72,8 @@ impl Manifests {
             binstall,
             cargo_crates_v1,
             installed_crates,
+            backups: Backups::new(&binstall_dir),
+            cargo_root: cargo_roots.to_path_buf(),
         })
     }
 
@@ -77,12 +89,79 @@ impl Manifests {
         &self.installed_crates
     }
 
+    /// Return the binstall record of crate `name` if it is installed.
+    pub fn get(&self, name: &str) -> Option<&CrateInfo> {
+        self.binstall.get(name)
+    }
+
+    pub fn backups(&self) -> &Backups {
+        &self.backups
+    }
+
+    /// Backup binaries of crate `name` currently installed in `install_path`,
+    /// see [`Backups::create`].
+    ///
+    /// Return `None` if the crate is not installed by binstall.
+    ///
+    /// This is a blocking function.
+    pub fn backup(
+        &self,
+        name: &str,
+        install_path: &Path,
+    ) -> Result<Option<Backup>, ManifestsError> {
+        self.get(name)
+            .map(|crate_info| self.backups.create(crate_info, install_path))
+            .transpose()
+            .map_err(ManifestsError::from)
+    }
+
+    /// Return the extra files of installed crates that are no longer provided
+    /// by `metadata_vec`, which should be removed once it is installed.
+    pub fn stale_extra_files(
+        &self,
+        metadata_vec: &[CrateInfo],
+    ) -> Result<Vec<PathBuf>, ManifestsError> {
+        let mut stale_extra_files = Vec::new();
+
+        for (metadata, old) in metadata_vec
+            .iter()
+            .filter_map(|metadata| Some((metadata, self.binstall.get(&metadata.name)?)))
+        {
+            for extra_file in &old.extra_files {
+                if metadata.extra_files.contains(extra_file) {
+                    continue;
+                }
+
+                // Never remove anything outside of the install root
+                if !Path::new(&**extra_file)
+                    .components()
+                    .all(|component| matches!(component, Component::Normal(_)))
+                {
+                    return Err(ManifestsError::InvalidExtraFilePath(extra_file.clone()));
+                }
+
+                stale_extra_files.push(self.cargo_root.join(&**extra_file));
+            }
+        }
+
+        Ok(stale_extra_files)
+    }
+
     pub fn update(mut self, metadata_vec: Vec<CrateInfo>) -> Result<(), ManifestsError> {
         self.rewind_cargo_crates_v1()?;
 
         CratesToml::append_to_file(&mut self.cargo_crates_v1, &metadata_vec)?;
 
-        for metadata in metadata_vec {
+        for mut metadata in metadata_vec {
+            // Versions installed side by side are kept on disk until
+            // explicitly removed, so keep tracking them.
+            if let Some(old) = self.binstall.get(&metadata.name) {
+                metadata
+                    .installed_versions
+                    .extend(old.installed_versions.iter().cloned());
+                metadata.installed_versions.sort_unstable();
+                metadata.installed_versions.dedup();
+            }
             self.binstall.replace(metadata);
         }
         self.binstall.overwrite()?;
@@ -10,6 +10,7 @@
 
 mod helpers;
 
+pub mod binstall_backups;
 pub mod binstall_crates_v1;
 pub mod cargo_config;
 pub mod cargo_crates_v1;
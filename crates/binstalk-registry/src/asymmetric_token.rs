@@ -0,0 +1,273 @@
+use std::{
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use aws_lc_rs::{
+    digest::{digest, SHA384},
+    encoding::{AsBigEndian, EcPublicKeyCompressedBin},
+    rand::SystemRandom,
+    signature::{EcdsaKeyPair, KeyPair, ECDSA_P384_SHA384_FIXED_SIGNING},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use binstalk_types::SecretString;
+use chrono::{DateTime, SecondsFormat};
+use compact_str::{format_compact, CompactString};
+use miette::Diagnostic;
+use serde::Serialize;
+use thiserror::Error as ThisError;
+
+const SECRET_KEY_PREFIX: &str = "k3.secret.";
+const TOKEN_HEADER: &str = "v3.public.";
+
+/// Length of a P-384 secret key.
+const SECRET_KEY_LEN: usize = 48;
+
+#[derive(Debug, ThisError, Diagnostic)]
+#[non_exhaustive]
+pub enum AsymmetricTokenError {
+    #[error("secret key is not a PASERK `k3.secret` key")]
+    InvalidSecretKey,
+
+    #[error("failed to sign the asymmetric token")]
+    Signing,
+}
+
+/// Secret key of a registry using asymmetric tokens (`cargo:paseto`), used to
+/// generate a PASETO `v3.public` token for each request as specified in
+/// [RFC 3231](https://rust-lang.github.io/rfcs/3231-cargo-asymmetric-tokens.html).
+pub struct AsymmetricKey {
+    key_pair: EcdsaKeyPair,
+    /// Compressed public key, part of the signed message.
+    public_key: Box<[u8]>,
+    /// PASERK id of the public key.
+    key_id: CompactString,
+    subject: Option<CompactString>,
+    /// Index url of the registry, as configured in cargo.
+    index_url: CompactString,
+}
+
+#[derive(Serialize)]
+struct Message<'a> {
+    iat: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sub: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    vers: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct Footer<'a> {
+    url: &'a str,
+    kip: &'a str,
+}
+
+impl AsymmetricKey {
+    /// * `secret_key` - PASERK `k3.secret` key, i.e. `secret-key` in
+    ///   cargo's credentials.
+    /// * `subject` - `secret-key-subject` in cargo's credentials.
+    /// * `index_url` - index url of the registry, as passed to
+    ///   `cargo install --index`.
+    pub fn new(
+        secret_key: &SecretString,
+        subject: Option<CompactString>,
+        index_url: CompactString,
+    ) -> Result<Self, AsymmetricTokenError> {
+        let scalar = secret_key
+            .strip_prefix(SECRET_KEY_PREFIX)
+            .and_then(|key| URL_SAFE_NO_PAD.decode(key).ok())
+            .filter(|scalar| scalar.len() == SECRET_KEY_LEN)
+            .ok_or(AsymmetricTokenError::InvalidSecretKey)?;
+
+        // RFC 5915 `ECPrivateKey` without the public key, which is derived
+        // from the secret key.
+        let mut der = Vec::with_capacity(64);
+        der.extend_from_slice(&[0x30, 0x3e, 0x02, 0x01, 0x01, 0x04, 0x30]);
+        der.extend_from_slice(&scalar);
+        // [0] namedCurve secp384r1
+        der.extend_from_slice(&[0xa0, 0x07, 0x06, 0x05, 0x2b, 0x81, 0x04, 0x00, 0x22]);
+
+        let key_pair = EcdsaKeyPair::from_private_key_der(&ECDSA_P384_SHA384_FIXED_SIGNING, &der)
+            .map_err(|_| AsymmetricTokenError::InvalidSecretKey)?;
+
+        let public_key: EcPublicKeyCompressedBin<'_> = key_pair
+            .public_key()
+            .as_be_bytes()
+            .map_err(|_| AsymmetricTokenError::InvalidSecretKey)?;
+        let public_key: Box<[u8]> = public_key.as_ref().into();
+
+        // PASERK `k3.pid` of the `k3.public` key
+        let paserk_public = format!("k3.public.{}", URL_SAFE_NO_PAD.encode(&public_key));
+        let hash = digest(&SHA384, format!("k3.pid.{paserk_public}").as_bytes());
+        let key_id = format_compact!("k3.pid.{}", URL_SAFE_NO_PAD.encode(&hash.as_ref()[..33]));
+
+        Ok(Self {
+            key_pair,
+            public_key,
+            key_id,
+            subject,
+            index_url,
+        })
+    }
+
+    /// Generate a token for a request reading `crate_name` at `version`.
+    pub(crate) fn token(
+        &self,
+        crate_name: Option<&str>,
+        version: Option<&str>,
+    ) -> Result<String, AsymmetricTokenError> {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let iat = DateTime::from_timestamp(now as i64, 0)
+            .unwrap_or_default()
+            .to_rfc3339_opts(SecondsFormat::Secs, true);
+
+        let message = serde_json::to_vec(&Message {
+            iat: &iat,
+            sub: self.subject.as_deref(),
+            name: crate_name,
+            vers: version,
+        })
+        .map_err(|_| AsymmetricTokenError::Signing)?;
+        let footer = serde_json::to_vec(&Footer {
+            url: &self.index_url,
+            kip: &self.key_id,
+        })
+        .map_err(|_| AsymmetricTokenError::Signing)?;
+
+        let signature = self
+            .key_pair
+            .sign(
+                &SystemRandom::new(),
+                &pre_auth_encode(&[
+                    &self.public_key,
+                    TOKEN_HEADER.as_bytes(),
+                    &message,
+                    &footer,
+                    b"",
+                ]),
+            )
+            .map_err(|_| AsymmetricTokenError::Signing)?;
+
+        let mut payload = message;
+        payload.extend_from_slice(signature.as_ref());
+
+        Ok(format!(
+            "{TOKEN_HEADER}{}.{}",
+            URL_SAFE_NO_PAD.encode(payload),
+            URL_SAFE_NO_PAD.encode(footer)
+        ))
+    }
+}
+
+impl fmt::Debug for AsymmetricKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AsymmetricKey")
+            .field("key_id", &self.key_id)
+            .field("subject", &self.subject)
+            .field("index_url", &self.index_url)
+            .finish_non_exhaustive()
+    }
+}
+
+/// PASETO's pre-authentication encoding.
+fn pre_auth_encode(pieces: &[&[u8]]) -> Vec<u8> {
+    let mut out = Vec::new();
+    out.extend_from_slice(&(pieces.len() as u64).to_le_bytes());
+    for piece in pieces {
+        out.extend_from_slice(&(piece.len() as u64).to_le_bytes());
+        out.extend_from_slice(piece);
+    }
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use aws_lc_rs::signature::{UnparsedPublicKey, ECDSA_P384_SHA384_FIXED};
+
+    use super::*;
+
+    const SECRET_KEY: &str =
+        "k3.secret.fNYVuMvBgOlljt9TDohnaYLblghqaHoQquVZwgR6X12cBFHZLFsaU3q7X3k1Zn36";
+
+    #[test]
+    fn test_pre_auth_encode() {
+        // Test vectors from the PASETO specification
+        assert_eq!(pre_auth_encode(&[]), b"\x00\x00\x00\x00\x00\x00\x00\x00");
+        assert_eq!(
+            pre_auth_encode(&[b"test"]),
+            b"\x01\x00\x00\x00\x00\x00\x00\x00\x04\x00\x00\x00\x00\x00\x00\x00test"
+        );
+    }
+
+    #[test]
+    fn test_token() {
+        let key = AsymmetricKey::new(
+            &SecretString::from_boxed_str(SECRET_KEY.into()),
+            Some("user".into()),
+            "sparse+https://registry.example.com/index/".into(),
+        )
+        .unwrap();
+
+        let token = key.token(Some("foo"), Some("1.0.0")).unwrap();
+
+        let (payload, footer) = token
+            .strip_prefix(TOKEN_HEADER)
+            .unwrap()
+            .split_once('.')
+            .unwrap();
+        let payload = URL_SAFE_NO_PAD.decode(payload).unwrap();
+        let footer = URL_SAFE_NO_PAD.decode(footer).unwrap();
+        let (message, signature) = payload.split_at(payload.len() - 96);
+
+        let message: serde_json::Value = serde_json::from_slice(message).unwrap();
+        assert_eq!(message["sub"], "user");
+        assert_eq!(message["name"], "foo");
+        assert_eq!(message["vers"], "1.0.0");
+        assert!(message.get("mutation").is_none());
+        assert!(message["iat"].as_str().unwrap().ends_with('Z'));
+
+        let footer_json: serde_json::Value = serde_json::from_slice(&footer).unwrap();
+        assert_eq!(
+            footer_json["url"],
+            "sparse+https://registry.example.com/index/"
+        );
+        assert_eq!(footer_json["kip"], key.key_id.as_str());
+
+        let public_key = key.key_pair.public_key().as_ref();
+        UnparsedPublicKey::new(&ECDSA_P384_SHA384_FIXED, public_key)
+            .verify(
+                &pre_auth_encode(&[
+                    &key.public_key,
+                    TOKEN_HEADER.as_bytes(),
+                    &payload[..payload.len() - 96],
+                    &footer,
+                    b"",
+                ]),
+                signature,
+            )
+            .unwrap();
+    }
+
+    #[test]
+    fn test_invalid_secret_key() {
+        for secret_key in [
+            "",
+            "k3.secret.AAAA",
+            "k4.secret.fNYVuMvBgOlljt9TDohnaYLblghqaHoQquVZwgR6X12cBFHZLFsaU3q7X3k1Zn36",
+        ] {
+            assert!(matches!(
+                AsymmetricKey::new(
+                    &SecretString::from_boxed_str(secret_key.into()),
+                    None,
+                    "https://example.com".into()
+                ),
+                Err(AsymmetricTokenError::InvalidSecretKey)
+            ));
+        }
+    }
+}
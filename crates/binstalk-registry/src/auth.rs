@@ -1,14 +1,40 @@
-use std::{fmt, sync::Arc};
+use std::{borrow::Cow, fmt, io, sync::Arc};
 
 use binstalk_types::SecretString;
 use compact_str::CompactString;
+use tokio::task::spawn_blocking;
 
-use crate::Registry;
+#[cfg(feature = "asymmetric-token")]
+use crate::AsymmetricKey;
+use crate::{Registry, RegistryError};
 
 #[derive(Clone, Debug)]
 pub struct RegistryAuth {
     registry_name: Option<CompactString>,
-    token: Arc<SecretString>,
+    credential: Credential,
+}
+
+/// Get a token from an external credential provider, see
+/// [`RegistryAuth::credential_provider`].
+type GetToken = dyn Fn() -> io::Result<SecretString> + Send + Sync;
+
+#[derive(Clone)]
+enum Credential {
+    Token(Arc<SecretString>),
+    Provider(Arc<GetToken>),
+    #[cfg(feature = "asymmetric-token")]
+    Asymmetric(Arc<AsymmetricKey>),
+}
+
+impl fmt::Debug for Credential {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Token(token) => f.debug_tuple("Token").field(token).finish(),
+            Self::Provider(_) => f.write_str("Provider"),
+            #[cfg(feature = "asymmetric-token")]
+            Self::Asymmetric(key) => f.debug_tuple("Asymmetric").field(key).finish(),
+        }
+    }
 }
 
 impl RegistryAuth {
@@ -18,13 +44,67 @@ impl RegistryAuth {
         } else {
             Some(Self {
                 registry_name,
-                token: Arc::new(token),
+                credential: Credential::Token(Arc::new(token)),
             })
         }
     }
 
-    pub fn token(&self) -> &str {
-        &self.token[..]
+    /// Authenticate with asymmetric tokens generated from `key` for each
+    /// request, i.e. cargo's `cargo:paseto` credential provider.
+    #[cfg(feature = "asymmetric-token")]
+    pub fn asymmetric(registry_name: Option<CompactString>, key: AsymmetricKey) -> Self {
+        Self {
+            registry_name,
+            credential: Credential::Asymmetric(Arc::new(key)),
+        }
+    }
+
+    /// Authenticate with tokens from an external credential provider.
+    ///
+    /// `get_token` is only called, on a blocking thread, for requests to
+    /// registries requiring authentication, so that e.g. a global provider
+    /// is not run for crates.io.
+    pub fn credential_provider(
+        registry_name: Option<CompactString>,
+        get_token: impl Fn() -> io::Result<SecretString> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            registry_name,
+            credential: Credential::Provider(Arc::new(get_token)),
+        }
+    }
+
+    /// Return the token, `None` if tokens are obtained or generated per
+    /// request.
+    pub fn token(&self) -> Option<&str> {
+        match &self.credential {
+            Credential::Token(token) => Some(&token[..]),
+            Credential::Provider(_) => None,
+            #[cfg(feature = "asymmetric-token")]
+            Credential::Asymmetric(_) => None,
+        }
+    }
+
+    /// Return the value of the `Authorization` header for a request reading
+    /// `crate_name` at `version`.
+    #[cfg_attr(not(feature = "asymmetric-token"), allow(unused_variables))]
+    pub(crate) async fn authorization(
+        &self,
+        crate_name: Option<&str>,
+        version: Option<&str>,
+    ) -> Result<Cow<'_, str>, RegistryError> {
+        match &self.credential {
+            Credential::Token(token) => Ok(Cow::Borrowed(&token[..])),
+            Credential::Provider(get_token) => {
+                let get_token = get_token.clone();
+                let token = spawn_blocking(move || get_token())
+                    .await?
+                    .map_err(RegistryError::CredentialProvider)?;
+                Ok(Cow::Owned(token[..].to_owned()))
+            }
+            #[cfg(feature = "asymmetric-token")]
+            Credential::Asymmetric(key) => Ok(Cow::Owned(key.token(crate_name, version)?)),
+        }
     }
 
     pub fn registry_name(&self) -> Option<&str> {
@@ -36,11 +116,24 @@ impl RegistryAuth {
 pub struct ResolvedRegistry {
     registry: Registry,
     auth: Option<RegistryAuth>,
+    /// Registry replaced by `registry` through cargo's source replacement.
+    replaced: Option<Registry>,
 }
 
 impl ResolvedRegistry {
     pub fn new(registry: Registry, auth: Option<RegistryAuth>) -> Self {
-        Self { registry, auth }
+        Self {
+            registry,
+            auth,
+            replaced: None,
+        }
+    }
+
+    /// Mark this registry as a replacement of `replaced`, which is then
+    /// recorded as the source of installed crates, like cargo does.
+    pub fn replacing(mut self, replaced: Registry) -> Self {
+        self.replaced = Some(replaced);
+        self
     }
 
     pub async fn fetch_crate_matched(
@@ -48,17 +141,31 @@ impl ResolvedRegistry {
         client: binstalk_downloader::remote::Client,
         crate_name: &str,
         version_req: &semver::VersionReq,
-    ) -> Result<
-        cargo_toml_workspace::cargo_toml::Manifest<binstalk_types::cargo_toml_binstall::Meta>,
-        crate::RegistryError,
-    > {
+        filter: &crate::VersionFilter,
+    ) -> Result<crate::MatchedManifest, crate::RegistryError> {
         self.registry
-            .fetch_crate_matched_with_auth(client, self.auth.as_ref(), crate_name, version_req)
+            .fetch_crate_matched_with_auth(
+                client,
+                self.auth.as_ref(),
+                crate_name,
+                version_req,
+                filter,
+            )
             .await
     }
 
+    /// Cache the index of the registry in `cache_dir`, see
+    /// [`Registry::with_cache_dir`].
+    pub fn with_cache_dir(mut self, cache_dir: &std::path::Path) -> Self {
+        self.registry = self.registry.with_cache_dir(cache_dir);
+        self
+    }
+
     pub fn crate_source(&self) -> Result<binstalk_types::crate_info::CrateSource, url::ParseError> {
-        self.registry.crate_source()
+        self.replaced
+            .as_ref()
+            .unwrap_or(&self.registry)
+            .crate_source()
     }
 
     pub fn cargo_install_index_arg(&self) -> String {
@@ -71,6 +178,7 @@ impl fmt::Debug for ResolvedRegistry {
         f.debug_struct("ResolvedRegistry")
             .field("registry", &self.registry)
             .field("auth", &self.auth)
+            .field("replaced", &self.replaced)
             .finish()
     }
 }
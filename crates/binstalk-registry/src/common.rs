@@ -1,4 +1,7 @@
-use std::borrow::Cow;
+use std::{
+    borrow::Cow,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
 use base16::{decode as decode_base16, encode_lower as encode_base16};
 use binstalk_downloader::{
@@ -8,15 +11,19 @@ use binstalk_downloader::{
 };
 use binstalk_types::cargo_toml_binstall::{Meta, TarBasedFmt};
 use cargo_toml_workspace::cargo_toml::Manifest;
+use chrono::DateTime;
 use compact_str::{format_compact, CompactString, ToCompactString};
 use leon::{Template, Values};
-use semver::{Version, VersionReq};
+use semver::{Comparator, Op as ComparatorOp, Version, VersionReq};
 use serde::Deserialize;
 use serde_json::Error as JsonError;
 use sha2::{Digest, Sha256};
 use tracing::{debug, instrument};
 
-use crate::{visitor::ManifestVisitor, RegistryAuth, RegistryError};
+use crate::{
+    visitor::{ExtractedManifest, ManifestVisitor},
+    ManifestCache, MissingPubtime, RegistryAuth, RegistryError, VersionFilter,
+};
 
 #[derive(Clone, Debug, Deserialize)]
 pub(super) struct RegistryConfig {
@@ -58,7 +65,7 @@ impl DataVerifier for Sha256Digest {
 }
 
 #[instrument(
-    skip(client, crate_url),
+    skip(client, crate_url, matched_version, cache),
     fields(
         crate_url = format_args!("{crate_url}"),
     ),
@@ -67,22 +74,62 @@ pub(super) async fn parse_manifest(
     client: Client,
     crate_name: &str,
     crate_url: Url,
-    MatchedVersion { version, cksum }: MatchedVersion,
+    matched_version: MatchedVersion,
     auth: Option<&RegistryAuth>,
+    cache: Option<&ManifestCache>,
 ) -> Result<Manifest<Meta>, RegistryError> {
+    if let Some(cache) = cache {
+        if let Some(extracted) = cache.load(crate_name, &matched_version).await {
+            debug!(
+                "Using cached Cargo.toml of {crate_name}@{}",
+                matched_version.version
+            );
+            return extracted.load_manifest();
+        }
+    }
+
     debug!("Fetching crate from: {crate_url} and extracting Cargo.toml from it");
 
+    let extracted = load_crate_manifest(
+        Download::from_response(
+            apply_auth(
+                client.get(crate_url),
+                auth,
+                Some(crate_name),
+                Some(&matched_version.version),
+            )
+            .await?
+            .send(true)
+            .await?,
+        ),
+        crate_name,
+        &matched_version,
+    )
+    .await?;
+
+    if let Some(cache) = cache {
+        cache.store(crate_name, &matched_version, &extracted).await;
+    }
+
+    extracted.load_manifest()
+}
+
+/// Extract `Cargo.toml` from the `.crate` tarball in `download`, verifying
+/// its checksum.
+pub(super) async fn load_crate_manifest(
+    download: Download<'static>,
+    crate_name: &str,
+    MatchedVersion { version, cksum, .. }: &MatchedVersion,
+) -> Result<ExtractedManifest, RegistryError> {
     let mut manifest_visitor = ManifestVisitor::new(format!("{crate_name}-{version}").into());
 
     let checksum = decode_base16(cksum.as_bytes()).map_err(RegistryError::from)?;
     let mut digest = Sha256Digest::new(checksum);
 
-    Download::from_response_with_data_verifier(
-        apply_auth(client.get(crate_url), auth).send(true).await?,
-        &mut digest,
-    )
-    .and_visit_tar(TarBasedFmt::Tgz, &mut manifest_visitor)
-    .await?;
+    download
+        .with_data_verifier(&mut digest)
+        .and_visit_tar(TarBasedFmt::Tgz, &mut manifest_visitor)
+        .await?;
 
     if !digest.validate() {
         Err(RegistryError::UnmatchedChecksum {
@@ -90,16 +137,37 @@ pub(super) async fn parse_manifest(
             actual: encode_base16(digest.actual.unwrap().as_slice()).into(),
         })
     } else {
-        manifest_visitor.load_manifest()
+        manifest_visitor.into_extracted()
     }
 }
 
-pub(super) fn apply_auth(request: RequestBuilder, auth: Option<&RegistryAuth>) -> RequestBuilder {
-    if let Some(auth) = auth {
-        request.header("Authorization", auth.token())
+/// * `crate_name`, `version` - crate read by the request, included in
+///   asymmetric tokens.
+pub(super) async fn apply_auth(
+    request: RequestBuilder,
+    auth: Option<&RegistryAuth>,
+    crate_name: Option<&str>,
+    version: Option<&str>,
+) -> Result<RequestBuilder, RegistryError> {
+    Ok(if let Some(auth) = auth {
+        request.header(
+            "Authorization",
+            &auth.authorization(crate_name, version).await?,
+        )
     } else {
         request
-    }
+    })
+}
+
+/// Return the name of the directory caching data of the registry at
+/// `index_url`, `<host>-<hash of the url>`, so that registries do not share
+/// cached data.
+pub(super) fn registry_cache_dir_name(index_url: &str) -> String {
+    let hash = encode_base16(&Sha256::digest(index_url.as_bytes())[..8]);
+    let url = Url::parse(index_url).ok();
+    let host = url.as_ref().and_then(Url::host_str).unwrap_or("index");
+
+    format!("{host}-{hash}")
 }
 
 /// Return components of crate prefix
@@ -128,7 +196,7 @@ pub(super) fn render_dl_template(
     dl_template: &str,
     crate_name: &str,
     (c1, c2): &(CompactString, Option<CompactString>),
-    MatchedVersion { version, cksum }: &MatchedVersion,
+    MatchedVersion { version, cksum, .. }: &MatchedVersion,
 ) -> Result<String, RegistryError> {
     let template = Template::parse(dl_template)?;
     if template.keys().next().is_some() {
@@ -174,32 +242,89 @@ pub(super) struct RegistryIndexEntry {
     vers: CompactString,
     yanked: bool,
     cksum: String,
+    /// Publish time in RFC 3339 format, only present in newer entries.
+    #[serde(default)]
+    pubtime: Option<CompactString>,
+}
+
+impl RegistryIndexEntry {
+    /// Return true if the entry is published after `cutoff` seconds since
+    /// the unix epoch, `None` if its publish time is unknown.
+    fn is_published_after(&self, cutoff: i64) -> Option<bool> {
+        let pubtime = self.pubtime.as_deref()?;
+
+        match DateTime::parse_from_rfc3339(pubtime) {
+            Ok(pubtime) => Some(pubtime.timestamp() > cutoff),
+            Err(err) => {
+                debug!(
+                    "Failed to parse pubtime '{pubtime}' of version {}: {err}",
+                    self.vers
+                );
+                None
+            }
+        }
+    }
 }
 
 pub(super) struct MatchedVersion {
     pub(super) version: CompactString,
     /// sha256 checksum encoded in base16
     pub(super) cksum: String,
+    pub(super) yanked: bool,
+}
+
+/// Return the version matched by `version_req` if it is `=x.y.z`, the only
+/// kind of requirement a yanked or too recent version is allowed to match.
+pub fn exact_version_req(version_req: &VersionReq) -> Option<Version> {
+    match version_req.comparators.as_slice() {
+        [Comparator {
+            op: ComparatorOp::Exact,
+            major,
+            minor: Some(minor),
+            patch: Some(patch),
+            pre,
+        }] => Some(Version {
+            major: *major,
+            minor: *minor,
+            patch: *patch,
+            pre: pre.clone(),
+            build: Default::default(),
+        }),
+        _ => None,
+    }
 }
 
 impl MatchedVersion {
     pub(super) fn find(
         it: &mut dyn Iterator<Item = Result<RegistryIndexEntry, JsonError>>,
         version_req: &VersionReq,
+        filter: &VersionFilter,
     ) -> Result<Self, RegistryError> {
+        let is_exact = exact_version_req(version_req).is_some();
+        let allow_yanked = filter.allow_yanked && is_exact;
+        // Versions published after this are too recent
+        let cutoff = filter
+            .min_release_age
+            .filter(|_| !is_exact)
+            .map(|min_release_age| {
+                let now = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default();
+                now.saturating_sub(min_release_age.as_duration()).as_secs() as i64
+            });
+
         let mut ret = Option::<(Self, Version)>::None;
+        let mut skipped_too_recent = false;
 
         for res in it {
             let entry = res.map_err(RegistryError::from)?;
 
-            if entry.yanked {
+            if entry.yanked && !allow_yanked {
                 continue;
             }
 
-            let num = entry.vers;
-
             // Parse out version
-            let Ok(ver) = Version::parse(&num) else {
+            let Ok(ver) = Version::parse(&entry.vers) else {
                 continue;
             };
 
@@ -208,9 +333,22 @@ impl MatchedVersion {
                 continue;
             }
 
+            if let Some(cutoff) = cutoff {
+                let too_recent = entry
+                    .is_published_after(cutoff)
+                    .unwrap_or(filter.missing_pubtime == MissingPubtime::Deny);
+
+                if too_recent {
+                    debug!("Skipping version {ver}, not old enough");
+                    skipped_too_recent = true;
+                    continue;
+                }
+            }
+
             let matched = Self {
-                version: num,
+                version: entry.vers,
                 cksum: entry.cksum,
+                yanked: entry.yanked,
             };
 
             if let Some((_, max_ver)) = &ret {
@@ -222,9 +360,77 @@ impl MatchedVersion {
             }
         }
 
-        ret.map(|(num, _)| num)
-            .ok_or_else(|| RegistryError::VersionMismatch {
+        match (ret, filter.min_release_age) {
+            (Some((matched, _)), _) => Ok(matched),
+            (None, Some(min_release_age)) if skipped_too_recent => {
+                Err(RegistryError::VersionTooRecent {
+                    req: version_req.clone(),
+                    min_release_age,
+                })
+            }
+            (None, _) => Err(RegistryError::VersionMismatch {
                 req: version_req.clone(),
-            })
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::Deserializer as JsonDeserializer;
+
+    use super::*;
+
+    const INDEX: &str = r#"
+{"name":"foo","vers":"1.0.0","cksum":"aa","yanked":false,"pubtime":"2020-01-01T00:00:00Z"}
+{"name":"foo","vers":"1.1.0","cksum":"bb","yanked":true,"pubtime":"2020-02-01T00:00:00Z"}
+{"name":"foo","vers":"1.2.0","cksum":"cc","yanked":false}
+{"name":"foo","vers":"1.3.0","cksum":"dd","yanked":false,"pubtime":"2999-01-01T00:00:00Z"}
+"#;
+
+    fn find(
+        version_req: &str,
+        filter: &VersionFilter,
+    ) -> Result<(CompactString, bool), RegistryError> {
+        MatchedVersion::find(
+            &mut JsonDeserializer::from_str(INDEX).into_iter(),
+            &VersionReq::parse(version_req).unwrap(),
+            filter,
+        )
+        .map(|matched| (matched.version, matched.yanked))
+    }
+
+    #[test]
+    fn test_find_yanked() {
+        let filter = VersionFilter::default();
+        assert_eq!(find("<1.2", &filter).unwrap(), ("1.0.0".into(), false));
+        find("=1.1.0", &filter).unwrap_err();
+
+        // Only honoured for exact requirements
+        let filter = VersionFilter {
+            allow_yanked: true,
+            ..Default::default()
+        };
+        assert_eq!(find("<1.2", &filter).unwrap(), ("1.0.0".into(), false));
+        find("^1.1, <1.2", &filter).unwrap_err();
+        assert_eq!(find("=1.1.0", &filter).unwrap(), ("1.1.0".into(), true));
+    }
+
+    #[test]
+    fn test_find_min_release_age() {
+        let mut filter = VersionFilter {
+            min_release_age: Some("7d".parse().unwrap()),
+            ..Default::default()
+        };
+        assert_eq!(find("*", &filter).unwrap().0, "1.2.0");
+        // Exact requirements are not filtered
+        assert_eq!(find("=1.3.0", &filter).unwrap().0, "1.3.0");
+        assert!(matches!(
+            find("^1.3", &filter),
+            Err(RegistryError::VersionTooRecent { .. })
+        ));
+
+        filter.missing_pubtime = MissingPubtime::Deny;
+        assert_eq!(find("*", &filter).unwrap().0, "1.0.0");
     }
 }
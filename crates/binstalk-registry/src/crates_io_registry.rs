@@ -166,7 +166,12 @@ pub async fn fetch_crate_cratesio_api(
         client,
         name,
         crate_url,
-        MatchedVersion { version, cksum },
+        MatchedVersion {
+            version,
+            cksum,
+            yanked: false,
+        },
+        None,
         None,
     )
     .await
@@ -0,0 +1,188 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use binstalk_types::cargo_toml_binstall::Meta;
+use cargo_toml_workspace::cargo_toml::Manifest;
+use compact_str::ToCompactString;
+use semver::{Version, VersionReq};
+use tokio::task::spawn_blocking;
+use tracing::{debug, instrument};
+
+use crate::{exact_version_req, MatchedManifest, MissingPubtime, RegistryError, VersionFilter};
+
+/// Cargo's `directory` source, unpacked crates on disk, e.g. vendored by
+/// `cargo vendor`.
+#[derive(Debug)]
+pub struct DirectoryRegistry {
+    path: PathBuf,
+}
+
+impl DirectoryRegistry {
+    /// * `path` - the directory containing the unpacked crates, each in a
+    ///   directory named `<crate>` or `<crate>-<version>`.
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// WARNING: This is a blocking operation.
+    fn find_crate_matched(
+        path: &Path,
+        crate_name: &str,
+        version_req: &VersionReq,
+        filter: &VersionFilter,
+    ) -> Result<Manifest<Meta>, RegistryError> {
+        // Vendored crates have no publish time and are never yanked.
+        let skip_all = filter.min_release_age.is_some()
+            && filter.missing_pubtime == MissingPubtime::Deny
+            && exact_version_req(version_req).is_none();
+
+        let mut found = false;
+        let mut ret = Option::<(Manifest<Meta>, Version)>::None;
+
+        for entry in fs::read_dir(path)? {
+            let entry = entry?;
+
+            let dir_name = entry.file_name();
+            let Some(dir_name) = dir_name.to_str() else {
+                continue;
+            };
+            if dir_name != crate_name
+                && !dir_name
+                    .strip_prefix(crate_name)
+                    .map_or(false, |suffix| suffix.starts_with('-'))
+            {
+                continue;
+            }
+
+            let manifest_path = entry.path().join("Cargo.toml");
+            let manifest = match Manifest::<Meta>::from_path_with_metadata(&manifest_path) {
+                Ok(manifest) => manifest,
+                Err(err) => {
+                    debug!("Failed to load {}: {err}", manifest_path.display());
+                    continue;
+                }
+            };
+
+            let Some(package) = manifest.package.as_ref() else {
+                continue;
+            };
+            if package.name != crate_name {
+                continue;
+            }
+            found = true;
+
+            let ver = package.version().clone();
+            if !version_req.matches(&ver) || skip_all {
+                continue;
+            }
+
+            if let Some((_, max_ver)) = &ret {
+                if ver > *max_ver {
+                    ret = Some((manifest, ver));
+                }
+            } else {
+                ret = Some((manifest, ver));
+            }
+        }
+
+        match (ret, filter.min_release_age) {
+            (Some((manifest, _)), _) => Ok(manifest),
+            (None, _) if !found => Err(RegistryError::NotFound(crate_name.into())),
+            (None, Some(min_release_age)) if skip_all => Err(RegistryError::VersionTooRecent {
+                req: version_req.clone(),
+                min_release_age,
+            }),
+            (None, _) => Err(RegistryError::VersionMismatch {
+                req: version_req.clone(),
+            }),
+        }
+    }
+
+    #[instrument(
+        skip(self, version_req),
+        fields(
+            version_req = format_args!("{version_req}"),
+        ),
+    )]
+    pub async fn fetch_crate_matched(
+        self: &Arc<Self>,
+        crate_name: &str,
+        version_req: &VersionReq,
+        filter: &VersionFilter,
+    ) -> Result<MatchedManifest, RegistryError> {
+        let name = crate_name.to_compact_string();
+        let version_req = version_req.clone();
+        let filter = filter.clone();
+        let this = self.clone();
+
+        let manifest = spawn_blocking(move || {
+            Self::find_crate_matched(&this.path, &name, &version_req, &filter)
+        })
+        .await??;
+
+        Ok(MatchedManifest {
+            manifest,
+            yanked: false,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn write_crate(path: &Path, dir_name: &str, name: &str, version: &str) {
+        let crate_path = path.join(dir_name);
+        fs::create_dir_all(crate_path.join("src")).unwrap();
+        fs::write(crate_path.join("src/main.rs"), "fn main() {}").unwrap();
+        fs::write(
+            crate_path.join("Cargo.toml"),
+            format!("[package]\nname = \"{name}\"\nversion = \"{version}\"\n"),
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_find_crate_matched() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let path = tempdir.path();
+
+        write_crate(path, "foo", "foo", "1.2.0");
+        write_crate(path, "foo-1.0.0", "foo", "1.0.0");
+        write_crate(path, "foo-bar", "foo-bar", "2.0.0");
+
+        let find = |version_req: &str| {
+            DirectoryRegistry::find_crate_matched(
+                path,
+                "foo",
+                &VersionReq::parse(version_req).unwrap(),
+                &VersionFilter::default(),
+            )
+            .map(|manifest| manifest.package().version().to_string())
+        };
+
+        assert_eq!(find("*").unwrap(), "1.2.0");
+        assert_eq!(find("<1.2").unwrap(), "1.0.0");
+        assert!(matches!(
+            find("^2"),
+            Err(RegistryError::VersionMismatch { .. })
+        ));
+
+        assert!(matches!(
+            DirectoryRegistry::find_crate_matched(
+                path,
+                "bar",
+                &VersionReq::STAR,
+                &VersionFilter::default()
+            ),
+            Err(RegistryError::NotFound(_))
+        ));
+    }
+}
@@ -1,44 +1,92 @@
-use std::{io, path::PathBuf, sync::Arc};
+use std::{
+    fs, io, mem,
+    num::NonZeroU32,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering::Relaxed},
+        Arc,
+    },
+};
 
 use binstalk_downloader::remote::Client;
-use binstalk_types::cargo_toml_binstall::Meta;
-use cargo_toml_workspace::cargo_toml::Manifest;
 use compact_str::{CompactString, ToCompactString};
+use fs_lock::FileLock;
+use gix::{
+    create,
+    open::{self, Permissions},
+    progress::Discard,
+    remote::{
+        fetch::{Shallow, Tags},
+        ref_map, Direction,
+    },
+    ObjectId, ThreadSafeRepository,
+};
 use once_cell::sync::OnceCell;
 use semver::VersionReq;
 use serde_json::{from_slice as json_from_slice, Deserializer as JsonDeserializer};
-use simple_git::{GitCancellationToken, GitUrl, Repository};
+use simple_git::GitUrl;
 use tempfile::TempDir;
 use tokio::task::spawn_blocking;
-use tracing::instrument;
+use tracing::{debug, instrument};
 use url::Url;
 
 use crate::{
-    crate_prefix_components, parse_manifest, render_dl_template, MatchedVersion, RegistryAuth,
-    RegistryConfig, RegistryError,
+    crate_prefix_components, parse_manifest, registry_cache_dir_name, render_dl_template,
+    ManifestCache, MatchedManifest, MatchedVersion, RegistryAuth, RegistryConfig, RegistryError,
+    VersionFilter,
 };
 
+/// Fetch the `HEAD` of the index into a ref of its own, so that it is used
+/// for negotiation on the next fetch.
+const FETCH_REFSPEC: &str = "+HEAD:refs/remotes/origin/HEAD";
+
+/// Every fetch adds a pack file to the persistent clone, which is cloned
+/// again once it has more than this many of them, same as cargo does, so
+/// that it does not grow without bound.
+const MAX_PACKS: usize = 100;
+
+type BoxError = Box<dyn std::error::Error + Send + Sync>;
+
 #[derive(Debug)]
 struct GitIndex {
-    _tempdir: TempDir,
-    repo: Repository,
+    _tempdir: Option<TempDir>,
+    /// Lock on the persistent clone, held while it is in use so that it is
+    /// not fetched or cloned again by another process meanwhile.
+    _lock: Option<FileLock>,
+    repo: ThreadSafeRepository,
+    /// Tree of the fetched `HEAD` commit.
+    tree: ObjectId,
     config: RegistryConfig,
 }
 
 impl GitIndex {
-    fn new(url: GitUrl, cancellation_token: GitCancellationToken) -> Result<Self, RegistryError> {
-        let tempdir = TempDir::new()?;
-
-        let repo = Repository::shallow_clone_bare(
-            url.clone(),
-            tempdir.as_ref(),
-            Some(cancellation_token),
-        )?;
+    /// Shallow clone the index at `url` into `index_dir` if it is not there
+    /// yet, otherwise fetch it incrementally.
+    ///
+    /// * `index_dir` - `None` to clone into a temporary directory.
+    ///
+    /// WARNING: This is a blocking operation.
+    fn new(
+        url: &GitUrl,
+        index_dir: Option<&Path>,
+        should_interrupt: &AtomicBool,
+    ) -> Result<Self, RegistryError> {
+        let (tempdir, lock, repo, tree) = match index_dir {
+            Some(index_dir) => {
+                let (lock, repo, tree) = Self::open_and_fetch(url, index_dir, should_interrupt)?;
+                (None, Some(lock), repo, tree)
+            }
+            None => {
+                let tempdir = TempDir::new()?;
+                let repo = Self::init(tempdir.path())?;
+                let tree = Self::fetch(&repo, url, should_interrupt)?;
+                (Some(tempdir), None, repo, tree)
+            }
+        };
 
         let config: RegistryConfig = {
-            let config = repo
-                .get_head_commit_entry_data_by_path("config.json")?
-                .ok_or_else(|| {
+            let config =
+                Self::read_entry(&repo, tree, Path::new("config.json"))?.ok_or_else(|| {
                     io::Error::new(
                         io::ErrorKind::NotFound,
                         format!("config.json not found in repository `{url}`"),
@@ -50,16 +98,180 @@ impl GitIndex {
 
         Ok(Self {
             _tempdir: tempdir,
+            _lock: lock,
             repo,
+            tree,
             config,
         })
     }
+
+    fn open_options() -> open::Options {
+        open::Options::default().permissions(Permissions::all())
+    }
+
+    fn init(path: &Path) -> Result<ThreadSafeRepository, RegistryError> {
+        ThreadSafeRepository::init_opts(
+            path,
+            create::Kind::Bare,
+            create::Options::default(),
+            Self::open_options(),
+        )
+        .map_err(|err| RegistryError::GitIndex(err.into()))
+    }
+
+    /// Lock the persistent clone in `index_dir`, open it, creating it if
+    /// needed, and fetch it.
+    ///
+    /// Return the lock, which must be held while the clone is in use.
+    fn open_and_fetch(
+        url: &GitUrl,
+        index_dir: &Path,
+        should_interrupt: &AtomicBool,
+    ) -> Result<(FileLock, ThreadSafeRepository, ObjectId), RegistryError> {
+        fs::create_dir_all(index_dir.parent().unwrap())?;
+
+        // Not `with_extension`, since the directory name contains the host
+        // name, e.g. `github.com-<hash>`.
+        let mut lock_file_name = index_dir.file_name().unwrap().to_owned();
+        lock_file_name.push(".lock");
+        let lock_path = index_dir.with_file_name(lock_file_name);
+        let lock = FileLock::new_exclusive(fs::File::create(&lock_path)?)?
+            .set_file_path(lock_path.as_path());
+
+        let repo = match ThreadSafeRepository::open_opts(index_dir, Self::open_options()) {
+            Ok(repo) if Self::count_packs(&repo)? > MAX_PACKS => {
+                debug!(
+                    "Git index at {} has more than {MAX_PACKS} pack files, cloning it again",
+                    index_dir.display()
+                );
+                drop(repo);
+                fs::remove_dir_all(index_dir)?;
+                Self::init(index_dir)?
+            }
+            Ok(repo) => repo,
+            Err(err) => {
+                if index_dir.exists() {
+                    debug!(
+                        "Failed to open git index at {}, cloning it again: {err}",
+                        index_dir.display()
+                    );
+                    fs::remove_dir_all(index_dir)?;
+                }
+                Self::init(index_dir)?
+            }
+        };
+
+        let tree = Self::fetch(&repo, url, should_interrupt)?;
+
+        Ok((lock, repo, tree))
+    }
+
+    fn count_packs(repo: &ThreadSafeRepository) -> io::Result<usize> {
+        let entries = match fs::read_dir(repo.path().join("objects").join("pack")) {
+            Ok(entries) => entries,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(err) => return Err(err),
+        };
+
+        let mut count = 0;
+        for entry in entries {
+            if entry?.path().extension() == Some("pack".as_ref()) {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+
+    /// Shallow fetch `HEAD` of `url`, return the tree of the fetched commit.
+    fn fetch(
+        repo: &ThreadSafeRepository,
+        url: &GitUrl,
+        should_interrupt: &AtomicBool,
+    ) -> Result<ObjectId, RegistryError> {
+        fn inner(
+            repo: &ThreadSafeRepository,
+            url: &GitUrl,
+            should_interrupt: &AtomicBool,
+        ) -> Result<ObjectId, BoxError> {
+            debug!("Fetching git index {url} into {}", repo.path().display());
+
+            let repo = repo.to_thread_local();
+            let outcome = repo
+                .remote_at(url.to_string().as_str())?
+                .with_refspecs(Some(FETCH_REFSPEC), Direction::Fetch)?
+                .with_fetch_tags(Tags::None)
+                .connect(Direction::Fetch)?
+                .prepare_fetch(Discard, ref_map::Options::default())?
+                .with_shallow(Shallow::DepthAtRemote(NonZeroU32::new(1).unwrap()))
+                .receive(Discard, should_interrupt)?;
+
+            let commit = outcome
+                .ref_map
+                .mappings
+                .iter()
+                .find_map(|mapping| mapping.remote.as_id())
+                .ok_or("HEAD not found in the remote repository")?;
+
+            let tree = repo.find_commit(commit)?.tree_id()?.detach();
+            Ok(tree)
+        }
+
+        inner(repo, url, should_interrupt).map_err(RegistryError::GitIndex)
+    }
+
+    /// WARNING: This is a blocking operation.
+    fn read_entry(
+        repo: &ThreadSafeRepository,
+        tree: ObjectId,
+        path: &Path,
+    ) -> Result<Option<Vec<u8>>, RegistryError> {
+        fn inner(
+            repo: &ThreadSafeRepository,
+            tree: ObjectId,
+            path: &Path,
+        ) -> Result<Option<Vec<u8>>, BoxError> {
+            let repo = repo.to_thread_local();
+            let entry = repo.find_tree(tree)?.peel_to_entry_by_path(path)?;
+
+            Ok(match entry {
+                Some(entry) => Some(mem::take(&mut entry.object()?.data)),
+                None => None,
+            })
+        }
+
+        inner(repo, tree, path).map_err(RegistryError::GitIndex)
+    }
+
+    /// WARNING: This is a blocking operation.
+    fn get_entry_data_by_path(&self, path: &Path) -> Result<Option<Vec<u8>>, RegistryError> {
+        Self::read_entry(&self.repo, self.tree, path)
+    }
+}
+
+/// Interrupt the git operation on drop, unless disarmed.
+struct InterruptOnDrop(Option<Arc<AtomicBool>>);
+
+impl InterruptOnDrop {
+    fn disarm(mut self) {
+        self.0 = None;
+    }
+}
+
+impl Drop for InterruptOnDrop {
+    fn drop(&mut self) {
+        if let Some(should_interrupt) = &self.0 {
+            should_interrupt.store(true, Relaxed);
+        }
+    }
 }
 
 #[derive(Debug)]
 struct GitRegistryInner {
     url: GitUrl,
     git_index: OnceCell<GitIndex>,
+    /// Persistent clone of the index, reused across runs.
+    index_dir: Option<PathBuf>,
+    manifest_cache: Option<ManifestCache>,
 }
 
 #[derive(Clone, Debug)]
@@ -70,6 +282,26 @@ impl GitRegistry {
         Self(Arc::new(GitRegistryInner {
             url,
             git_index: Default::default(),
+            index_dir: None,
+            manifest_cache: None,
+        }))
+    }
+
+    /// Keep a clone of the index in `cache_dir` and fetch it incrementally
+    /// instead of cloning it on every run, and cache the manifests extracted
+    /// from crates.
+    pub fn with_cache_dir(self, cache_dir: &Path) -> Self {
+        let url = self.0.url.to_string();
+
+        Self(Arc::new(GitRegistryInner {
+            url: self.0.url.clone(),
+            git_index: Default::default(),
+            index_dir: Some(
+                cache_dir
+                    .join("git-index")
+                    .join(registry_cache_dir_name(&url)),
+            ),
+            manifest_cache: Some(ManifestCache::new(cache_dir, &url)),
         }))
     }
 
@@ -79,10 +311,11 @@ impl GitRegistry {
 
     /// WARNING: This is a blocking operation.
     fn find_crate_matched_ver(
-        repo: &Repository,
+        git_index: &GitIndex,
         crate_name: &str,
         (c1, c2): &(CompactString, Option<CompactString>),
         version_req: &VersionReq,
+        filter: &VersionFilter,
     ) -> Result<MatchedVersion, RegistryError> {
         let mut path = PathBuf::with_capacity(128);
         path.push(&**c1);
@@ -91,13 +324,14 @@ impl GitRegistry {
         }
 
         path.push(&*crate_name.to_lowercase());
-        let crate_versions = repo
-            .get_head_commit_entry_data_by_path(path)?
+        let crate_versions = git_index
+            .get_entry_data_by_path(&path)?
             .ok_or_else(|| RegistryError::NotFound(crate_name.into()))?;
 
         MatchedVersion::find(
             &mut JsonDeserializer::from_slice(&crate_versions).into_iter(),
             version_req,
+            filter,
         )
     }
 
@@ -113,28 +347,31 @@ impl GitRegistry {
         auth: Option<&RegistryAuth>,
         name: &str,
         version_req: &VersionReq,
-    ) -> Result<Manifest<Meta>, RegistryError> {
+        filter: &VersionFilter,
+    ) -> Result<MatchedManifest, RegistryError> {
         let crate_prefix = crate_prefix_components(name)?;
         let crate_name = name.to_compact_string();
         let version_req = version_req.clone();
+        let filter = filter.clone();
         let this = self.clone();
 
-        let cancellation_token = GitCancellationToken::default();
-        // Cancel git operation if the future is cancelled (dropped).
-        let cancel_on_drop = cancellation_token.clone().cancel_on_drop();
+        let should_interrupt = Arc::new(AtomicBool::new(false));
+        // Interrupt git operation if the future is cancelled (dropped).
+        let interrupt_on_drop = InterruptOnDrop(Some(should_interrupt.clone()));
 
         let (matched_version, dl_url, auth_required) = spawn_blocking(move || {
-            let GitIndex {
-                _tempdir: _,
-                repo,
-                config,
-            } = this
-                .0
-                .git_index
-                .get_or_try_init(|| GitIndex::new(this.0.url.clone(), cancellation_token))?;
-
-            let matched_version =
-                Self::find_crate_matched_ver(repo, &crate_name, &crate_prefix, &version_req)?;
+            let git_index = this.0.git_index.get_or_try_init(|| {
+                GitIndex::new(&this.0.url, this.0.index_dir.as_deref(), &should_interrupt)
+            })?;
+            let config = &git_index.config;
+
+            let matched_version = Self::find_crate_matched_ver(
+                git_index,
+                &crate_name,
+                &crate_prefix,
+                &version_req,
+                &filter,
+            )?;
 
             let url = Url::parse(&render_dl_template(
                 &config.dl,
@@ -148,7 +385,7 @@ impl GitRegistry {
         .await??;
 
         // Git operation done, disarm it
-        cancel_on_drop.disarm();
+        interrupt_on_drop.disarm();
 
         let auth = if auth_required {
             let Some(auth) = auth else {
@@ -162,6 +399,111 @@ impl GitRegistry {
             None
         };
 
-        parse_manifest(client, name, dl_url, matched_version, auth).await
+        let yanked = matched_version.yanked;
+        let manifest = parse_manifest(
+            client,
+            name,
+            dl_url,
+            matched_version,
+            auth,
+            self.0.manifest_cache.as_ref(),
+        )
+        .await?;
+
+        Ok(MatchedManifest { manifest, yanked })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::process::Command;
+
+    use super::*;
+
+    fn git(repo: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .current_dir(repo)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    fn commit_index(repo: &Path, versions: &str) {
+        fs::create_dir_all(repo.join("3/f")).unwrap();
+        fs::write(repo.join("3/f/foo"), versions).unwrap();
+        git(repo, &["add", "."]);
+        git(repo, &["commit", "-q", "-m", "Update foo"]);
+    }
+
+    #[test]
+    fn test_incremental_fetch() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let remote = tempdir.path().join("remote");
+        fs::create_dir(&remote).unwrap();
+
+        git(&remote, &["init", "-q"]);
+        fs::write(
+            remote.join("config.json"),
+            r#"{"dl":"https://example.com/{crate}/{version}/download"}"#,
+        )
+        .unwrap();
+        commit_index(&remote, "{\"vers\":\"1.0.0\"}\n");
+
+        let url: GitUrl = format!("file://{}", remote.display()).parse().unwrap();
+        let index_dir = tempdir.path().join("cache/git-index/example.com-0123abcd");
+        let should_interrupt = AtomicBool::new(false);
+
+        let read_foo = |git_index: &GitIndex| {
+            String::from_utf8(
+                git_index
+                    .get_entry_data_by_path(Path::new("3/f/foo"))
+                    .unwrap()
+                    .unwrap(),
+            )
+            .unwrap()
+        };
+
+        let git_index = GitIndex::new(&url, Some(&index_dir), &should_interrupt).unwrap();
+        // The clone is locked while in use
+        let lock_path = tempdir
+            .path()
+            .join("cache/git-index/example.com-0123abcd.lock");
+        assert!(FileLock::new_try_exclusive(fs::File::open(&lock_path).unwrap()).is_err());
+        assert_eq!(
+            git_index.config.dl,
+            "https://example.com/{crate}/{version}/download"
+        );
+        assert_eq!(read_foo(&git_index), "{\"vers\":\"1.0.0\"}\n");
+        assert!(git_index
+            .get_entry_data_by_path(Path::new("3/b/bar"))
+            .unwrap()
+            .is_none());
+        drop(git_index);
+
+        commit_index(&remote, "{\"vers\":\"1.0.0\"}\n{\"vers\":\"1.1.0\"}\n");
+
+        let git_index = GitIndex::new(&url, Some(&index_dir), &should_interrupt).unwrap();
+        assert_eq!(
+            read_foo(&git_index),
+            "{\"vers\":\"1.0.0\"}\n{\"vers\":\"1.1.0\"}\n"
+        );
+        drop(git_index);
+
+        // Cloned again once it has too many pack files
+        let pack_dir = index_dir.join("objects/pack");
+        for i in 0..=MAX_PACKS {
+            fs::write(pack_dir.join(format!("pack-{i}.pack")), "").unwrap();
+        }
+        commit_index(&remote, "{\"vers\":\"1.1.0\"}\n");
+
+        let git_index = GitIndex::new(&url, Some(&index_dir), &should_interrupt).unwrap();
+        assert_eq!(read_foo(&git_index), "{\"vers\":\"1.1.0\"}\n");
+        assert!(GitIndex::count_packs(&git_index.repo).unwrap() < MAX_PACKS);
+
+        // Without a persistent clone
+        let git_index = GitIndex::new(&url, None, &should_interrupt).unwrap();
+        assert_eq!(read_foo(&git_index), "{\"vers\":\"1.1.0\"}\n");
     }
 }
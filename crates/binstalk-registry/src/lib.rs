@@ -1,6 +1,6 @@
 #![cfg_attr(docsrs, feature(doccfg))]
 
-use std::{fmt, io, str::FromStr, sync::Arc};
+use std::{fmt, io, path::Path, str::FromStr, sync::Arc};
 
 use base16::DecodeError as Base16DecodeError;
 use binstalk_downloader::{
@@ -25,6 +25,11 @@ use url::{ParseError as UrlParseError, Url};
 mod auth;
 pub use auth::{RegistryAuth, ResolvedRegistry};
 
+#[cfg(feature = "asymmetric-token")]
+mod asymmetric_token;
+#[cfg(feature = "asymmetric-token")]
+pub use asymmetric_token::{AsymmetricKey, AsymmetricTokenError};
+
 #[cfg(feature = "git")]
 pub use simple_git::{GitError, GitUrl, GitUrlParseError};
 
@@ -33,6 +38,7 @@ mod vfs;
 mod visitor;
 
 mod common;
+pub use common::exact_version_req;
 use common::*;
 
 #[cfg(feature = "git")]
@@ -45,9 +51,24 @@ mod crates_io_registry;
 #[cfg(any(feature = "crates_io_api", test))]
 pub use crates_io_registry::fetch_crate_cratesio_api;
 
+mod sparse_cache;
+use sparse_cache::SparseIndexCache;
+
+mod manifest_cache;
+use manifest_cache::ManifestCache;
+
 mod sparse_registry;
 pub use sparse_registry::SparseRegistry;
 
+mod local_registry;
+pub use local_registry::LocalRegistry;
+
+mod directory_registry;
+pub use directory_registry::DirectoryRegistry;
+
+mod version_filter;
+pub use version_filter::{MissingPubtime, ParseReleaseAgeError, ReleaseAge, VersionFilter};
+
 #[derive(Debug, ThisError, Diagnostic)]
 #[diagnostic(severity(error), code(binstall::cargo_registry))]
 #[non_exhaustive]
@@ -82,6 +103,15 @@ pub enum RegistryError {
     #[error("no version matching requirement '{req}'")]
     VersionMismatch { req: semver::VersionReq },
 
+    #[error("no version matching requirement '{req}' released at least {min_release_age} ago")]
+    #[diagnostic(help(
+        "Request an exact version `=x.y.z` to install a more recent version anyway."
+    ))]
+    VersionTooRecent {
+        req: semver::VersionReq,
+        min_release_age: ReleaseAge,
+    },
+
     #[error("Failed to parse cargo manifest: {0}")]
     #[diagnostic(help("If you used --manifest-path, check the Cargo.toml syntax."))]
     CargoManifest(#[from] Box<CargoTomlError>),
@@ -102,11 +132,22 @@ pub enum RegistryError {
     #[error("Failed to shallow clone git repository: {0}")]
     GitError(#[from] GitError),
 
+    #[cfg(feature = "git")]
+    #[error("Failed to fetch git index: {0}")]
+    GitIndex(#[source] Box<dyn std::error::Error + Send + Sync>),
+
     #[error("registry requires authentication: {0}")]
     #[diagnostic(
-        help("Configure Cargo credentials for this registry, for example with `cargo login` or `CARGO_REGISTRIES_<NAME>_TOKEN` when `cargo:token` is active.")
+        help("Configure Cargo credentials for this registry, for example with `cargo login` or `CARGO_REGISTRIES_<NAME>_TOKEN` when `cargo:token` is active, or `CARGO_REGISTRIES_<NAME>_SECRET_KEY` when `cargo:paseto` is active.")
     )]
     AuthenticationRequired(Box<Url>),
+
+    #[error("Failed to get a token from the credential provider: {0}")]
+    CredentialProvider(#[source] io::Error),
+
+    #[cfg(feature = "asymmetric-token")]
+    #[error(transparent)]
+    AsymmetricToken(#[from] AsymmetricTokenError),
 }
 
 impl From<CargoTomlError> for RegistryError {
@@ -115,6 +156,14 @@ impl From<CargoTomlError> for RegistryError {
     }
 }
 
+/// Manifest of the crate version matched in the registry.
+#[derive(Debug)]
+pub struct MatchedManifest {
+    pub manifest: Manifest<Meta>,
+    /// The matched version has been yanked from the registry.
+    pub yanked: bool,
+}
+
 #[derive(Clone, Debug)]
 #[non_exhaustive]
 pub enum Registry {
@@ -122,6 +171,12 @@ pub enum Registry {
 
     #[cfg(feature = "git")]
     Git(GitRegistry),
+
+    /// Cargo's `local-registry` source.
+    Local(Arc<LocalRegistry>),
+
+    /// Cargo's `directory` source.
+    Directory(Arc<DirectoryRegistry>),
 }
 
 impl Default for Registry {
@@ -186,6 +241,20 @@ impl Registry {
         }
     }
 
+    /// Cache the index of sparse and git registries and the manifests
+    /// extracted from crates in `cache_dir`, local registries are returned
+    /// as-is.
+    pub fn with_cache_dir(self, cache_dir: &Path) -> Self {
+        match self {
+            Self::Sparse(registry) => Self::Sparse(Arc::new(
+                SparseRegistry::new(registry.url().clone()).with_cache_dir(cache_dir),
+            )),
+            #[cfg(feature = "git")]
+            Self::Git(registry) => Self::Git(registry.with_cache_dir(cache_dir)),
+            registry => registry,
+        }
+    }
+
     /// Fetch the latest crate with `crate_name` and with version matching
     /// `version_req`.
     pub async fn fetch_crate_matched(
@@ -194,8 +263,15 @@ impl Registry {
         crate_name: &str,
         version_req: &VersionReq,
     ) -> Result<Manifest<Meta>, RegistryError> {
-        self.fetch_crate_matched_with_auth(client, None, crate_name, version_req)
-            .await
+        self.fetch_crate_matched_with_auth(
+            client,
+            None,
+            crate_name,
+            version_req,
+            &VersionFilter::default(),
+        )
+        .await
+        .map(|matched| matched.manifest)
     }
 
     pub(crate) async fn fetch_crate_matched_with_auth(
@@ -204,17 +280,28 @@ impl Registry {
         auth: Option<&RegistryAuth>,
         crate_name: &str,
         version_req: &VersionReq,
-    ) -> Result<Manifest<Meta>, RegistryError> {
+        filter: &VersionFilter,
+    ) -> Result<MatchedManifest, RegistryError> {
         match self {
             Self::Sparse(sparse_registry) => {
                 sparse_registry
-                    .fetch_crate_matched(client, auth, crate_name, version_req)
+                    .fetch_crate_matched(client, auth, crate_name, version_req, filter)
                     .await
             }
             #[cfg(feature = "git")]
             Self::Git(git_registry) => {
                 git_registry
-                    .fetch_crate_matched(client, auth, crate_name, version_req)
+                    .fetch_crate_matched(client, auth, crate_name, version_req, filter)
+                    .await
+            }
+            Self::Local(local_registry) => {
+                local_registry
+                    .fetch_crate_matched(crate_name, version_req, filter)
+                    .await
+            }
+            Self::Directory(directory_registry) => {
+                directory_registry
+                    .fetch_crate_matched(crate_name, version_req, filter)
                     .await
             }
         }
@@ -228,6 +315,8 @@ impl Registry {
                 Url::parse(&registry.url().to_string()).map(MaybeOwned::Owned)
             }
             Registry::Sparse(registry) => Ok(MaybeOwned::Borrowed(registry.url())),
+            Registry::Local(registry) => path_to_url(registry.path()).map(MaybeOwned::Owned),
+            Registry::Directory(registry) => path_to_url(registry.path()).map(MaybeOwned::Owned),
         }
     }
 
@@ -238,6 +327,7 @@ impl Registry {
             #[cfg(feature = "git")]
             Registry::Git(_) => SourceType::Git,
             Registry::Sparse(_) => SourceType::Sparse,
+            Registry::Local(_) | Registry::Directory(_) => SourceType::Registry,
         };
 
         Ok(match (registry.as_str(), source_type) {
@@ -257,16 +347,26 @@ impl Registry {
             #[cfg(feature = "git")]
             Registry::Git(registry) => registry.url().to_string(),
             Registry::Sparse(registry) => format!("sparse+{}", registry.url()),
+            // Not supported by `cargo install --index`, cargo can only use
+            // them through source replacement.
+            Registry::Local(registry) => registry.path().display().to_string(),
+            Registry::Directory(registry) => registry.path().display().to_string(),
         }
     }
 }
 
+fn path_to_url(path: &Path) -> Result<Url, UrlParseError> {
+    Url::from_directory_path(path).map_err(|()| UrlParseError::RelativeUrlWithoutBase)
+}
+
 impl fmt::Display for Registry {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             #[cfg(feature = "git")]
             Registry::Git(registry) => fmt::Display::fmt(&registry.url(), f),
             Registry::Sparse(registry) => fmt::Display::fmt(&registry.url(), f),
+            Registry::Local(registry) => fmt::Display::fmt(&registry.path().display(), f),
+            Registry::Directory(registry) => fmt::Display::fmt(&registry.path().display(), f),
         }
     }
 }
@@ -0,0 +1,110 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use binstalk_downloader::{bytes::Bytes, download::Download};
+use compact_str::{CompactString, ToCompactString};
+use semver::VersionReq;
+use serde_json::Deserializer as JsonDeserializer;
+use tokio::task::spawn_blocking;
+use tracing::{debug, instrument};
+
+use crate::{
+    crate_prefix_components, load_crate_manifest, MatchedManifest, MatchedVersion, RegistryError,
+    VersionFilter,
+};
+
+/// Cargo's `local-registry` source, an index and `.crate` files on disk,
+/// e.g. created by `cargo local-registry`.
+#[derive(Debug)]
+pub struct LocalRegistry {
+    path: PathBuf,
+}
+
+impl LocalRegistry {
+    /// * `path` - the directory containing the `index` directory and the
+    ///   `.crate` files.
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// WARNING: This is a blocking operation.
+    fn find_crate_matched_ver(
+        path: &Path,
+        crate_name: &str,
+        (c1, c2): &(CompactString, Option<CompactString>),
+        version_req: &VersionReq,
+        filter: &VersionFilter,
+    ) -> Result<(MatchedVersion, Bytes), RegistryError> {
+        let mut index_path = path.join("index");
+        index_path.push(&**c1);
+        if let Some(c2) = c2 {
+            index_path.push(&**c2);
+        }
+        index_path.push(&*crate_name.to_lowercase());
+
+        let crate_versions = match fs::read(&index_path) {
+            Ok(crate_versions) => crate_versions,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                return Err(RegistryError::NotFound(crate_name.into()))
+            }
+            Err(err) => return Err(err.into()),
+        };
+
+        let matched_version = MatchedVersion::find(
+            &mut JsonDeserializer::from_slice(&crate_versions).into_iter(),
+            version_req,
+            filter,
+        )?;
+
+        let crate_path = path.join(format!("{crate_name}-{}.crate", matched_version.version));
+        debug!("Reading crate from: {}", crate_path.display());
+
+        let crate_file = fs::read(crate_path)?;
+
+        Ok((matched_version, crate_file.into()))
+    }
+
+    #[instrument(
+        skip(self, version_req),
+        fields(
+            version_req = format_args!("{version_req}"),
+        ),
+    )]
+    pub async fn fetch_crate_matched(
+        self: &Arc<Self>,
+        crate_name: &str,
+        version_req: &VersionReq,
+        filter: &VersionFilter,
+    ) -> Result<MatchedManifest, RegistryError> {
+        let crate_prefix = crate_prefix_components(crate_name)?;
+        let name = crate_name.to_compact_string();
+        let version_req = version_req.clone();
+        let filter = filter.clone();
+        let this = self.clone();
+
+        let (matched_version, crate_file) = spawn_blocking(move || {
+            Self::find_crate_matched_ver(&this.path, &name, &crate_prefix, &version_req, &filter)
+        })
+        .await??;
+
+        let manifest = load_crate_manifest(
+            Download::from_bytes(crate_file),
+            crate_name,
+            &matched_version,
+        )
+        .await?
+        .load_manifest()?;
+
+        Ok(MatchedManifest {
+            manifest,
+            yanked: matched_version.yanked,
+        })
+    }
+}
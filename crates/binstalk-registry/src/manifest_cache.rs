@@ -0,0 +1,155 @@
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use tempfile::NamedTempFile;
+use tokio::task::spawn_blocking;
+use tracing::debug;
+
+use crate::{common::registry_cache_dir_name, visitor::ExtractedManifest, MatchedVersion};
+
+/// Manifests extracted from `.crate` tarballs cached on disk, to avoid
+/// downloading the whole tarball again to resolve the same version.
+///
+/// Entries are keyed by the registry and the checksum of the tarball in the
+/// registry index, so a cached manifest is only used for exactly the same
+/// crate content.
+#[derive(Debug)]
+pub(super) struct ManifestCache {
+    dir: PathBuf,
+}
+
+impl ManifestCache {
+    /// * `cache_dir` - directory shared by the caches of all registries.
+    /// * `index_url` - url of the registry index.
+    pub(super) fn new(cache_dir: &Path, index_url: &str) -> Self {
+        Self {
+            dir: cache_dir
+                .join("manifests")
+                .join(registry_cache_dir_name(index_url)),
+        }
+    }
+
+    fn path(
+        &self,
+        crate_name: &str,
+        MatchedVersion { version, cksum, .. }: &MatchedVersion,
+    ) -> PathBuf {
+        let mut path = self.dir.join(crate_name.to_lowercase());
+        path.push(format!("{version}-{cksum}.json"));
+        path
+    }
+
+    /// Load the cached manifest of `crate_name` with `matched_version`, if any.
+    pub(super) async fn load(
+        &self,
+        crate_name: &str,
+        matched_version: &MatchedVersion,
+    ) -> Option<ExtractedManifest> {
+        let path = self.path(crate_name, matched_version);
+
+        let res = spawn_blocking(move || {
+            let file = io::BufReader::new(fs::File::open(path)?);
+            Ok::<_, io::Error>(serde_json::from_reader(file)?)
+        })
+        .await;
+
+        match res {
+            Ok(Ok(extracted)) => Some(extracted),
+            Ok(Err(err)) if err.kind() == io::ErrorKind::NotFound => None,
+            Ok(Err(err)) => {
+                debug!("Failed to load cached manifest: {err}");
+                None
+            }
+            Err(err) => {
+                debug!("Failed to load cached manifest: {err}");
+                None
+            }
+        }
+    }
+
+    /// Store `extracted`, ignoring any error since the cache is only an
+    /// optimisation.
+    pub(super) async fn store(
+        &self,
+        crate_name: &str,
+        matched_version: &MatchedVersion,
+        extracted: &ExtractedManifest,
+    ) {
+        let path = self.path(crate_name, matched_version);
+
+        let content = match serde_json::to_vec(extracted) {
+            Ok(content) => content,
+            Err(err) => {
+                debug!("Failed to serialise manifest to cache: {err}");
+                return;
+            }
+        };
+
+        let res = spawn_blocking(move || {
+            let dir = path.parent().unwrap();
+            fs::create_dir_all(dir)?;
+
+            let mut file = NamedTempFile::new_in(dir)?;
+            io::Write::write_all(&mut file, &content)?;
+            file.persist(&path)?;
+
+            Ok::<_, io::Error>(())
+        })
+        .await;
+
+        match res {
+            Ok(Ok(())) => (),
+            Ok(Err(err)) => debug!("Failed to cache manifest: {err}"),
+            Err(err) => debug!("Failed to cache manifest: {err}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_roundtrip() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let cache = ManifestCache::new(tempdir.path(), "https://index.crates.io/");
+
+        let matched_version = MatchedVersion {
+            version: "1.0.0".into(),
+            cksum: "aa".into(),
+            yanked: false,
+        };
+        assert!(cache.load("foo", &matched_version).await.is_none());
+
+        let extracted: ExtractedManifest = serde_json::from_str(
+            r#"{
+                "cargo_toml": "[package]\nname = \"foo\"\nversion = \"1.0.0\"\n",
+                "vfs": { "": ["Cargo.toml", "src"], "src": ["main.rs"] }
+            }"#,
+        )
+        .unwrap();
+        cache.store("foo", &matched_version, &extracted).await;
+
+        let manifest = cache
+            .load("foo", &matched_version)
+            .await
+            .unwrap()
+            .load_manifest()
+            .unwrap();
+        assert_eq!(manifest.bin.len(), 1);
+        assert_eq!(manifest.bin[0].name.as_deref(), Some("foo"));
+
+        // Different registry
+        let other_cache = ManifestCache::new(tempdir.path(), "https://registry.example.com/index/");
+        assert!(other_cache.load("foo", &matched_version).await.is_none());
+
+        // Different checksum
+        let matched_version = MatchedVersion {
+            cksum: "bb".into(),
+            ..matched_version
+        };
+        assert!(cache.load("foo", &matched_version).await.is_none());
+    }
+}
@@ -0,0 +1,176 @@
+use std::{
+    fs,
+    io::{self, BufRead, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use binstalk_downloader::{bytes::Bytes, remote::header};
+use compact_str::CompactString;
+use serde::{Deserialize, Serialize};
+use tempfile::NamedTempFile;
+use tokio::task::spawn_blocking;
+use tracing::debug;
+use url::Url;
+
+use crate::common::registry_cache_dir_name;
+
+/// Files of a sparse index cached on disk along with the validators of the
+/// response, to send conditional requests like cargo does.
+#[derive(Debug)]
+pub(super) struct SparseIndexCache {
+    dir: PathBuf,
+}
+
+/// First line of a cache file, followed by the cached body.
+#[derive(Deserialize, Serialize)]
+struct CacheHeader {
+    etag: Option<CompactString>,
+    last_modified: Option<CompactString>,
+}
+
+pub(super) struct CachedFile {
+    header: CacheHeader,
+    pub(super) body: Bytes,
+}
+
+impl SparseIndexCache {
+    /// * `cache_dir` - directory shared by the caches of all registries.
+    pub(super) fn new(cache_dir: &Path, url: &Url) -> Self {
+        Self {
+            dir: cache_dir
+                .join("index")
+                .join(registry_cache_dir_name(url.as_str())),
+        }
+    }
+
+    /// Load the cached copy of `path`, if any.
+    pub(super) async fn load(&self, path: &str) -> Option<CachedFile> {
+        let path = self.dir.join(path);
+
+        let res = spawn_blocking(move || {
+            let mut file = io::BufReader::new(fs::File::open(&path)?);
+
+            let mut header = String::new();
+            file.read_line(&mut header)?;
+            let header = serde_json::from_str(&header)?;
+
+            let mut body = Vec::new();
+            file.read_to_end(&mut body)?;
+
+            Ok::<_, io::Error>(CachedFile {
+                header,
+                body: body.into(),
+            })
+        })
+        .await;
+
+        match res {
+            Ok(Ok(cached)) => Some(cached),
+            Ok(Err(err)) if err.kind() == io::ErrorKind::NotFound => None,
+            Ok(Err(err)) => {
+                debug!("Failed to load cached index file: {err}");
+                None
+            }
+            Err(err) => {
+                debug!("Failed to load cached index file: {err}");
+                None
+            }
+        }
+    }
+
+    /// Store `body` of the response with `headers` for `path`, ignoring any
+    /// error since the cache is only an optimisation.
+    pub(super) async fn store(&self, path: &str, headers: &header::HeaderMap, body: Bytes) {
+        let get_header = |name| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(CompactString::from)
+        };
+        let header = CacheHeader {
+            etag: get_header(header::ETAG),
+            last_modified: get_header(header::LAST_MODIFIED),
+        };
+        if header.etag.is_none() && header.last_modified.is_none() {
+            // Cannot be revalidated
+            return;
+        }
+
+        let path = self.dir.join(path);
+
+        let res = spawn_blocking(move || {
+            let dir = path.parent().unwrap();
+            fs::create_dir_all(dir)?;
+
+            let mut file = NamedTempFile::new_in(dir)?;
+            serde_json::to_writer(&mut file, &header)?;
+            file.write_all(b"\n")?;
+            file.write_all(&body)?;
+            file.persist(&path)?;
+
+            Ok::<_, io::Error>(())
+        })
+        .await;
+
+        match res {
+            Ok(Ok(())) => (),
+            Ok(Err(err)) => debug!("Failed to cache index file: {err}"),
+            Err(err) => debug!("Failed to cache index file: {err}"),
+        }
+    }
+}
+
+impl CachedFile {
+    /// Headers to make the request conditional on the cached copy being stale.
+    pub(super) fn conditional_headers(&self) -> impl Iterator<Item = (&'static str, &str)> {
+        // `If-None-Match` takes precedence, no need to send both.
+        let (if_none_match, if_modified_since) = match &self.header.etag {
+            Some(etag) => (Some(etag.as_str()), None),
+            None => (None, self.header.last_modified.as_deref()),
+        };
+
+        if_none_match
+            .map(|etag| ("If-None-Match", etag))
+            .into_iter()
+            .chain(if_modified_since.map(|date| ("If-Modified-Since", date)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_roundtrip() {
+        let tempdir = tempfile::tempdir().unwrap();
+        let cache = SparseIndexCache::new(
+            tempdir.path(),
+            &Url::parse("https://index.crates.io/").unwrap(),
+        );
+
+        assert!(cache.load("3/f/foo").await.is_none());
+
+        let body = Bytes::from_static(b"{\"vers\":\"1.0.0\"}\n");
+
+        // Not cached without validators
+        cache
+            .store("3/f/foo", &header::HeaderMap::new(), body.clone())
+            .await;
+        assert!(cache.load("3/f/foo").await.is_none());
+
+        let mut headers = header::HeaderMap::new();
+        headers.insert(header::ETAG, "\"abc\"".parse().unwrap());
+        headers.insert(
+            header::LAST_MODIFIED,
+            "Wed, 21 Oct 2015 07:28:00 GMT".parse().unwrap(),
+        );
+        cache.store("3/f/foo", &headers, body.clone()).await;
+
+        let cached = cache.load("3/f/foo").await.unwrap();
+        assert_eq!(cached.body, body);
+        assert_eq!(
+            cached.conditional_headers().collect::<Vec<_>>(),
+            [("If-None-Match", "\"abc\"")]
+        );
+    }
+}
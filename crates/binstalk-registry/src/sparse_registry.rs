@@ -1,22 +1,35 @@
-use binstalk_downloader::remote::{Client, StatusCode};
-use binstalk_types::cargo_toml_binstall::Meta;
-use cargo_toml_workspace::cargo_toml::Manifest;
+use std::path::Path;
+
+use binstalk_downloader::{
+    bytes::Bytes,
+    remote::{Client, RequestBuilder, Response, StatusCode},
+};
 use compact_str::CompactString;
 use semver::VersionReq;
 use serde_json::Deserializer as JsonDeserializer;
 use tokio::sync::OnceCell;
-use tracing::instrument;
+use tracing::{debug, instrument};
 use url::Url;
 
 use crate::{
-    apply_auth, crate_prefix_components, parse_manifest, render_dl_template, MatchedVersion,
-    RegistryAuth, RegistryConfig, RegistryError,
+    apply_auth, crate_prefix_components, parse_manifest, render_dl_template, ManifestCache,
+    MatchedManifest, MatchedVersion, RegistryAuth, RegistryConfig, RegistryError, SparseIndexCache,
+    VersionFilter,
 };
 
 #[derive(Debug)]
 pub struct SparseRegistry {
     url: Url,
     config: OnceCell<RegistryConfig>,
+    cache: Option<SparseIndexCache>,
+    manifest_cache: Option<ManifestCache>,
+}
+
+/// File of the index, either up-to-date or a response with a status other
+/// than OK to be handled by the caller.
+enum IndexFile {
+    Fresh(Bytes),
+    Response(Response),
 }
 
 impl SparseRegistry {
@@ -25,9 +38,20 @@ impl SparseRegistry {
         Self {
             url,
             config: Default::default(),
+            cache: None,
+            manifest_cache: None,
         }
     }
 
+    /// Cache the files of the index in `cache_dir` and revalidate them with
+    /// conditional requests instead of refetching them, and cache the
+    /// manifests extracted from crates.
+    pub fn with_cache_dir(mut self, cache_dir: &Path) -> Self {
+        self.cache = Some(SparseIndexCache::new(cache_dir, &self.url));
+        self.manifest_cache = Some(ManifestCache::new(cache_dir, self.url.as_str()));
+        self
+    }
+
     pub fn url(&self) -> &Url {
         &self.url
     }
@@ -43,33 +67,89 @@ impl SparseRegistry {
                     let mut url = self.url.clone();
                     url.path_segments_mut().unwrap().push("config.json");
 
-                    let response = client.get(url.clone()).send(false).await?;
-                    let response = match response.status() {
-                        StatusCode::UNAUTHORIZED => {
+                    let response = match self
+                        .send_cached(client.get(url.clone()), "config.json")
+                        .await?
+                    {
+                        IndexFile::Fresh(body) => body,
+                        IndexFile::Response(response)
+                            if response.status() == StatusCode::UNAUTHORIZED =>
+                        {
                             let Some(auth) = auth else {
                                 return Err(RegistryError::AuthenticationRequired(Box::new(url)));
                             };
 
-                            apply_auth(client.get(url), Some(auth)).send(true).await?
+                            match self
+                                .send_cached(
+                                    apply_auth(client.get(url), Some(auth), None, None).await?,
+                                    "config.json",
+                                )
+                                .await?
+                            {
+                                IndexFile::Fresh(body) => body,
+                                IndexFile::Response(response) => {
+                                    response.error_for_status()?.bytes().await?
+                                }
+                            }
+                        }
+                        IndexFile::Response(response) => {
+                            response.error_for_status()?.bytes().await?
                         }
-                        _ => response.error_for_status()?,
                     };
 
-                    response.json().await.map_err(RegistryError::from)
+                    serde_json::from_slice(&response).map_err(RegistryError::from)
                 })
             })
             .await
     }
 
-    /// `url` must be a valid http(s) url.
+    /// Send `request` for `path` of the index, conditional on the cached
+    /// copy being stale if there is one.
+    async fn send_cached(
+        &self,
+        mut request: RequestBuilder,
+        path: &str,
+    ) -> Result<IndexFile, RegistryError> {
+        let cached = match &self.cache {
+            Some(cache) => cache.load(path).await,
+            None => None,
+        };
+
+        if let Some(cached) = &cached {
+            for (key, value) in cached.conditional_headers() {
+                request = request.header(key, value);
+            }
+        }
+
+        let response = request.send(false).await?;
+
+        match (response.status(), cached) {
+            (StatusCode::NOT_MODIFIED, Some(cached)) => {
+                debug!("{path} is not modified, using the cached copy");
+                Ok(IndexFile::Fresh(cached.body))
+            }
+            (StatusCode::OK, _) => {
+                let headers = response.headers().clone();
+                let body = response.bytes().await?;
+                if let Some(cache) = &self.cache {
+                    cache.store(path, &headers, body.clone()).await;
+                }
+                Ok(IndexFile::Fresh(body))
+            }
+            _ => Ok(IndexFile::Response(response)),
+        }
+    }
+
     async fn find_crate_matched_ver(
+        &self,
         client: &Client,
-        mut url: Url,
         auth: Option<&RegistryAuth>,
         crate_name: &str,
         (c1, c2): &(CompactString, Option<CompactString>),
         version_req: &VersionReq,
+        filter: &VersionFilter,
     ) -> Result<MatchedVersion, RegistryError> {
+        let mut url = self.url.clone();
         {
             let mut path = url.path_segments_mut().unwrap();
 
@@ -81,28 +161,42 @@ impl SparseRegistry {
             path.push(&crate_name.to_lowercase());
         }
 
-        let response = apply_auth(client.get(url.clone()), auth)
-            .send(false)
-            .await?;
-        let body = match response.status() {
-            StatusCode::OK => response.bytes().await.map_err(RegistryError::from)?,
-            StatusCode::NOT_FOUND
-            | StatusCode::GONE
-            | StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS => {
-                return Err(RegistryError::NotFound(crate_name.into()));
-            }
-            StatusCode::UNAUTHORIZED => {
-                return Err(RegistryError::AuthenticationRequired(Box::new(url)));
-            }
-            _ => response
-                .error_for_status()?
-                .bytes()
-                .await
-                .map_err(RegistryError::from)?,
+        let mut path = c1.clone();
+        if let Some(c2) = c2 {
+            path.push('/');
+            path.push_str(c2);
+        }
+        path.push('/');
+        path.push_str(&crate_name.to_lowercase());
+
+        let body = match self
+            .send_cached(
+                apply_auth(client.get(url.clone()), auth, Some(crate_name), None).await?,
+                &path,
+            )
+            .await?
+        {
+            IndexFile::Fresh(body) => body,
+            IndexFile::Response(response) => match response.status() {
+                StatusCode::NOT_FOUND
+                | StatusCode::GONE
+                | StatusCode::UNAVAILABLE_FOR_LEGAL_REASONS => {
+                    return Err(RegistryError::NotFound(crate_name.into()));
+                }
+                StatusCode::UNAUTHORIZED => {
+                    return Err(RegistryError::AuthenticationRequired(Box::new(url)));
+                }
+                _ => response
+                    .error_for_status()?
+                    .bytes()
+                    .await
+                    .map_err(RegistryError::from)?,
+            },
         };
         MatchedVersion::find(
             &mut JsonDeserializer::from_slice(&body).into_iter(),
             version_req,
+            filter,
         )
     }
 
@@ -119,7 +213,8 @@ impl SparseRegistry {
         auth: Option<&RegistryAuth>,
         crate_name: &str,
         version_req: &VersionReq,
-    ) -> Result<Manifest<Meta>, RegistryError> {
+        filter: &VersionFilter,
+    ) -> Result<MatchedManifest, RegistryError> {
         let crate_prefix = crate_prefix_components(crate_name)?;
         let registry_config = self.get_config(&client, auth).await?;
         let auth = if registry_config.auth_required {
@@ -133,15 +228,16 @@ impl SparseRegistry {
         } else {
             None
         };
-        let matched_version = Self::find_crate_matched_ver(
-            &client,
-            self.url.clone(),
-            auth,
-            crate_name,
-            &crate_prefix,
-            version_req,
-        )
-        .await?;
+        let matched_version = self
+            .find_crate_matched_ver(
+                &client,
+                auth,
+                crate_name,
+                &crate_prefix,
+                version_req,
+                filter,
+            )
+            .await?;
         let dl_url = Url::parse(&render_dl_template(
             &registry_config.dl,
             crate_name,
@@ -149,6 +245,17 @@ impl SparseRegistry {
             &matched_version,
         )?)?;
 
-        parse_manifest(client, crate_name, dl_url, matched_version, auth).await
+        let yanked = matched_version.yanked;
+        let manifest = parse_manifest(
+            client,
+            crate_name,
+            dl_url,
+            matched_version,
+            auth,
+            self.manifest_cache.as_ref(),
+        )
+        .await?;
+
+        Ok(MatchedManifest { manifest, yanked })
     }
 }
@@ -0,0 +1,145 @@
+use std::{fmt, str::FromStr, time::Duration};
+
+use compact_str::{format_compact, CompactString};
+use serde::{Deserialize, Serialize};
+use thiserror::Error as ThisError;
+
+/// Restrictions on the versions matched in the registry index.
+#[derive(Clone, Debug, Default)]
+pub struct VersionFilter {
+    /// Let a yanked version match if the version requirement is exact,
+    /// i.e. `=x.y.z`.
+    pub allow_yanked: bool,
+    /// Ignore versions published less than this long ago, unless the
+    /// version requirement is exact.
+    pub min_release_age: Option<ReleaseAge>,
+    /// Whether to ignore versions without publish time in the index when
+    /// `min_release_age` is set.
+    pub missing_pubtime: MissingPubtime,
+}
+
+/// What to do with index entries without `pubtime` when a minimum release
+/// age is required.
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum MissingPubtime {
+    /// Consider the version old enough.
+    #[default]
+    Allow,
+    /// Ignore the version.
+    Deny,
+}
+
+/// Minimum age of a release, written as a number followed by `w`, `d`, `h`
+/// or `m` (weeks, days, hours or minutes), e.g. `7d`.
+///
+/// A number without unit is in days.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Deserialize, Serialize)]
+#[serde(try_from = "CompactString", into = "CompactString")]
+pub struct ReleaseAge {
+    value: u64,
+    unit: ReleaseAgeUnit,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum ReleaseAgeUnit {
+    Week,
+    Day,
+    Hour,
+    Minute,
+}
+
+impl ReleaseAgeUnit {
+    const fn secs(self) -> u64 {
+        match self {
+            Self::Week => 7 * 24 * 60 * 60,
+            Self::Day => 24 * 60 * 60,
+            Self::Hour => 60 * 60,
+            Self::Minute => 60,
+        }
+    }
+
+    const fn suffix(self) -> char {
+        match self {
+            Self::Week => 'w',
+            Self::Day => 'd',
+            Self::Hour => 'h',
+            Self::Minute => 'm',
+        }
+    }
+}
+
+impl ReleaseAge {
+    pub fn as_duration(&self) -> Duration {
+        Duration::from_secs(self.value.saturating_mul(self.unit.secs()))
+    }
+}
+
+#[derive(Debug, ThisError)]
+#[error("invalid release age `{0}`, expected a number followed by `w`, `d`, `h` or `m`, e.g. `7d`")]
+pub struct ParseReleaseAgeError(CompactString);
+
+impl FromStr for ReleaseAge {
+    type Err = ParseReleaseAgeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (value, unit) = match s.char_indices().last() {
+            Some((i, 'w')) => (&s[..i], ReleaseAgeUnit::Week),
+            Some((i, 'd')) => (&s[..i], ReleaseAgeUnit::Day),
+            Some((i, 'h')) => (&s[..i], ReleaseAgeUnit::Hour),
+            Some((i, 'm')) => (&s[..i], ReleaseAgeUnit::Minute),
+            _ => (s, ReleaseAgeUnit::Day),
+        };
+
+        value
+            .trim_end()
+            .parse()
+            .map(|value| Self { value, unit })
+            .map_err(|_| ParseReleaseAgeError(s.into()))
+    }
+}
+
+impl TryFrom<CompactString> for ReleaseAge {
+    type Error = ParseReleaseAgeError;
+
+    fn try_from(s: CompactString) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<ReleaseAge> for CompactString {
+    fn from(age: ReleaseAge) -> Self {
+        format_compact!("{age}")
+    }
+}
+
+impl fmt::Display for ReleaseAge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}", self.value, self.unit.suffix())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_release_age() {
+        let age: ReleaseAge = "7d".parse().unwrap();
+        assert_eq!(age.as_duration(), Duration::from_secs(7 * 24 * 60 * 60));
+        assert_eq!(age.to_string(), "7d");
+
+        assert_eq!("2w".parse::<ReleaseAge>().unwrap().to_string(), "2w");
+        assert_eq!(
+            "12h".parse::<ReleaseAge>().unwrap().as_duration(),
+            Duration::from_secs(12 * 60 * 60)
+        );
+        assert_eq!("3".parse::<ReleaseAge>().unwrap().to_string(), "3d");
+
+        "".parse::<ReleaseAge>().unwrap_err();
+        "d".parse::<ReleaseAge>().unwrap_err();
+        "7y".parse::<ReleaseAge>().unwrap_err();
+        "-1d".parse::<ReleaseAge>().unwrap_err();
+    }
+}
@@ -6,11 +6,12 @@ use std::{
 
 use cargo_toml_workspace::cargo_toml::AbstractFilesystem;
 use normalize_path::NormalizePath;
+use serde::{Deserialize, Serialize};
 
 /// This type stores the filesystem structure for the crate tarball
 /// extracted in memory and can be passed to
 /// `cargo_toml::Manifest::complete_from_abstract_filesystem`.
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Deserialize, Serialize)]
 pub(super) struct Vfs(BTreeMap<Box<Path>, HashSet<Box<str>>>);
 
 impl Vfs {
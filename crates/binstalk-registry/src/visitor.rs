@@ -1,9 +1,13 @@
-use std::path::{Path, PathBuf};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
 
 use binstalk_downloader::download::{DownloadError, TarEntriesVisitor, TarEntry};
 use binstalk_types::cargo_toml_binstall::Meta;
 use cargo_toml_workspace::cargo_toml::{Manifest, Value};
 use normalize_path::NormalizePath;
+use serde::{Deserialize, Serialize};
 use tokio::io::AsyncReadExt;
 use tracing::debug;
 
@@ -65,12 +69,32 @@ impl TarEntriesVisitor for ManifestVisitor {
 }
 
 impl ManifestVisitor {
+    pub(super) fn into_extracted(self) -> Result<ExtractedManifest, RegistryError> {
+        let cargo_toml = String::from_utf8(self.cargo_toml_content)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+        Ok(ExtractedManifest {
+            cargo_toml,
+            vfs: self.vfs,
+        })
+    }
+}
+
+/// `Cargo.toml` and the filesystem structure extracted from a crate tarball,
+/// which is all needed to load its manifest.
+#[derive(Debug, Deserialize, Serialize)]
+pub(super) struct ExtractedManifest {
+    cargo_toml: String,
+    vfs: Vfs,
+}
+
+impl ExtractedManifest {
     /// Load binstall metadata using the extracted information stored in memory.
-    pub(super) fn load_manifest(self) -> Result<Manifest<Meta>, RegistryError> {
+    pub(super) fn load_manifest(&self) -> Result<Manifest<Meta>, RegistryError> {
         debug!("Loading manifest directly from extracted file");
 
         // Load and parse manifest
-        let mut manifest = Manifest::from_slice_with_metadata(&self.cargo_toml_content)?;
+        let mut manifest = Manifest::from_slice_with_metadata(self.cargo_toml.as_bytes())?;
         debug!("Manifest: {manifest:?}");
         // Checks vfs for binary output names
         manifest.complete_from_abstract_filesystem::<Value, _>(&self.vfs, None)?;
@@ -84,6 +84,10 @@ pub struct PkgMeta {
 
     /// Target specific overrides
     pub overrides: PkgOverrides,
+
+    /// Files other than binaries in packages to install, e.g. man pages
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub extra_files: Vec<ExtraFile>,
 }
 
 impl PkgMeta {
@@ -155,6 +159,8 @@ impl PkgMeta {
             },
 
             overrides: Default::default(),
+
+            extra_files: self.extra_files.clone(),
         }
     }
 }
@@ -219,6 +225,36 @@ pub struct BinMeta {
     pub path: String,
 }
 
+/// A file other than binaries in packages to install.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct ExtraFile {
+    /// Path template of the file in packages, supporting the same keys
+    /// as `bin-dir` with `bin` being the crate name
+    pub source: String,
+
+    /// Kind of the file, which decides where it is installed
+    pub kind: ExtraFileKind,
+}
+
+/// Kind of [`ExtraFile`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ExtraFileKind {
+    /// Man page, installed to `share/man/man{section}`
+    Man,
+    /// Installed to `share/bash-completion/completions`
+    BashCompletion,
+    /// Installed to `share/zsh/site-functions`
+    ZshCompletion,
+    /// Installed to `share/fish/vendor_completions.d`
+    FishCompletion,
+    /// Installed to `share/licenses/{name}`
+    License,
+    /// Installed to `share/doc/{name}`
+    Doc,
+}
+
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "kebab-case")]
 pub struct PkgSigning {
@@ -260,6 +296,31 @@ mod tests {
         });
     }
 
+    #[test]
+    fn test_extra_files_parse() {
+        let json = json!({
+            "extra-files": [
+                { "source": "{ name }-{ target }/doc/{ name }.1", "kind": "man" },
+                { "source": "completions/_{ name }", "kind": "zsh-completion" },
+            ],
+        });
+        let meta: PkgMeta = serde_json::from_value(json).unwrap();
+        assert_eq!(
+            meta.extra_files,
+            [
+                ExtraFile {
+                    source: "{ name }-{ target }/doc/{ name }.1".to_owned(),
+                    kind: ExtraFileKind::Man,
+                },
+                ExtraFile {
+                    source: "completions/_{ name }".to_owned(),
+                    kind: ExtraFileKind::ZshCompletion,
+                },
+            ]
+        );
+        assert_eq!(meta.merge_overrides([]).extra_files, meta.extra_files);
+    }
+
     #[test]
     fn test_pkg_overrides_parse_target_name() {
         let json = json!({
@@ -14,9 +14,24 @@ pub struct TargetTriple {
     pub env: Environment,
     pub vendor: Vendor,
     pub family: Option<Family>,
+    /// x86-64 psABI microarchitecture level of the machine the binaries
+    /// will run on, from 1 to 4, only set for x86_64 targets.
+    pub cpu_level: Option<u8>,
 }
 
 impl TargetTriple {
+    /// Set the x86-64 psABI microarchitecture level, which is ignored if the
+    /// target is not x86_64.
+    pub fn with_cpu_level(mut self, cpu_level: Option<u8>) -> Self {
+        if matches!(
+            self.arch,
+            ArchOr::Arch(Architecture::X86_64 | Architecture::X86_64h)
+        ) {
+            self.cpu_level = cpu_level;
+        }
+        self
+    }
+
     /// Returns a list of all the `cfg(...)` options that apply to this triple.
     pub fn cfgs(&self) -> Vec<Cfg> {
         let mut options = vec![
@@ -28,6 +43,11 @@ impl TargetTriple {
         if let Some(family) = self.family {
             options.push(CfgOption::Family(family));
         }
+        // A machine supporting a level also supports all lower levels,
+        // similar to `target_feature`.
+        if let Some(cpu_level) = self.cpu_level {
+            options.extend((1..=cpu_level).map(CfgOption::CpuLevel));
+        }
         options.into_iter().flat_map(CfgOption::into_cfgs).collect()
     }
 }
@@ -53,6 +73,7 @@ impl FromStr for TargetTriple {
             env: triple.environment,
             vendor: triple.vendor,
             family: Family::from_os(triple.operating_system),
+            cpu_level: None,
         })
     }
 }
@@ -65,6 +86,8 @@ pub enum CfgOption<'a> {
     Env(Environment),
     Vendor(&'a Vendor),
     Family(Family),
+    /// x86-64 psABI microarchitecture level, e.g. `target_cpu_level = "v3"`.
+    CpuLevel(u8),
 }
 
 impl<'a> CfgOption<'a> {
@@ -75,6 +98,7 @@ impl<'a> CfgOption<'a> {
             Self::Env(_) => "target_env",
             Self::Vendor(_) => "target_vendor",
             Self::Family(_) => "target_family",
+            Self::CpuLevel(_) => "target_cpu_level",
         }
     }
 
@@ -89,6 +113,7 @@ impl<'a> CfgOption<'a> {
             Self::Env(env) => env.into_str(),
             Self::Vendor(vendor) => vendor.as_str().into(),
             Self::Family(family) => family.to_string().into(),
+            Self::CpuLevel(level) => format!("v{level}").into(),
         }
     }
 
@@ -382,6 +407,23 @@ mod tests {
         assert!(cfgs.iter().any(|c| matches!(c, Cfg::KeyPair(ident, value) if ident.name == "target_family" && value == "unix")));
     }
 
+    #[test]
+    fn test_target_triple_cfgs_cpu_level() {
+        let triple: TargetTriple = "x86_64-unknown-linux-gnu".parse().unwrap();
+        let cfgs = triple.with_cpu_level(Some(3)).cfgs();
+
+        assert_eq!(cfgs.len(), 9);
+        for level in ["v1", "v2", "v3"] {
+            assert!(cfgs.iter().any(|c| matches!(c, Cfg::KeyPair(ident, value) if ident.name == "target_cpu_level" && value == level)));
+        }
+        assert!(!cfgs
+            .iter()
+            .any(|c| matches!(c, Cfg::KeyPair(_, value) if value == "v4")));
+
+        let triple: TargetTriple = "aarch64-unknown-linux-gnu".parse().unwrap();
+        assert_eq!(triple.with_cpu_level(Some(3)).cpu_level, None);
+    }
+
     #[test]
     fn test_target_triple_cfgs_windows() {
         let triple: TargetTriple = "x86_64-pc-windows-msvc".parse().unwrap();
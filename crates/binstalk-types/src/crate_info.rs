@@ -1,6 +1,6 @@
 //! Common structure for crate information for post-install manifests.
 
-use std::{borrow, cmp, hash};
+use std::{borrow, cmp, collections::BTreeMap, hash};
 
 use compact_str::CompactString;
 use maybe_owned::MaybeOwned;
@@ -22,8 +22,33 @@ pub struct CrateInfo {
     pub version_req: CompactString,
     pub current_version: Version,
     pub source: CrateSource,
+    /// SPDX license expression from the crate manifest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub license: Option<CompactString>,
+    /// The installed version has been yanked from the registry, installed
+    /// with `--allow-yanked`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub yanked: bool,
     pub target: CompactString,
     pub bins: Vec<CompactString>,
+    /// Versions installed side by side as `<bin>-v<version>`, sorted,
+    /// any of which `<bin>` can be symlinked to.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub installed_versions: Vec<Version>,
+    /// Binaries installed under a different name, as original name to
+    /// installed name (both without binary extension).
+    ///
+    /// `bins` contains the installed names.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub bin_renames: BTreeMap<CompactString, CompactString>,
+    /// Runtime the launchers of WASI modules are created for, or `direct`
+    /// if the modules can be executed directly.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub wasi_runtime: Option<CompactString>,
+    /// Files other than binaries installed, e.g. man pages, relative to
+    /// the install root.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub extra_files: Vec<CompactString>,
 }
 
 impl borrow::Borrow<str> for CrateInfo {
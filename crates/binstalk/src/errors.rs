@@ -434,6 +434,106 @@ pub enum BinstallError {
     )]
     CargoInstallDoesNotSupportInstallPath,
 
+    /// No backup to roll back to was found for the crate.
+    ///
+    /// - Code: `binstall::rollback::no_backup`
+    /// - Exit: 101
+    #[error("no backup of crate {0} found to roll back to")]
+    #[diagnostic(
+        severity(error),
+        code(binstall::rollback::no_backup),
+        help("Backups are only made when upgrading or reinstalling a crate tracked by binstall.")
+    )]
+    NoBackup(CompactString),
+
+    /// No version matching the requirement is installed side by side.
+    ///
+    /// - Code: `binstall::switch::version_not_installed`
+    /// - Exit: 102
+    #[error("no installed version of crate {crate_name} matches {version_req}")]
+    #[diagnostic(
+        severity(error),
+        code(binstall::switch::version_not_installed),
+        help("Install the version with `--versioned` first.")
+    )]
+    VersionNotInstalled {
+        crate_name: CompactString,
+        version_req: CompactString,
+    },
+
+    /// An installed binary failed the post-install smoke test.
+    ///
+    /// - Code: `binstall::smoke_test`
+    /// - Exit: 103
+    #[error("smoke test of binary {bin} failed: {reason}")]
+    #[diagnostic(
+        severity(error),
+        code(binstall::smoke_test),
+        help("stderr of the binary:\n{stderr}")
+    )]
+    SmokeTestFailed {
+        bin: CompactString,
+        reason: CompactString,
+        stderr: Box<str>,
+    },
+
+    /// The license of the crate is not allowed by the license policy.
+    ///
+    /// - Code: `binstall::license_policy`
+    /// - Exit: 104
+    #[error("license {} is not allowed: {reason}", .license.as_deref().unwrap_or("(none)"))]
+    #[diagnostic(
+        severity(error),
+        code(binstall::license_policy),
+        help("Check `allowed_licenses` and `denied_licenses` under `[policy]` in the settings.")
+    )]
+    LicenseNotAllowed {
+        license: Option<CompactString>,
+        reason: CompactString,
+    },
+
+    /// The crate version is affected by advisories and `--deny-advisories`
+    /// is passed.
+    ///
+    /// - Code: `binstall::advisories`
+    /// - Exit: 105
+    #[error("v{version} is affected by advisories: {advisories}")]
+    #[diagnostic(
+        severity(error),
+        code(binstall::advisories),
+        help("Install a version not affected, or remove `--deny-advisories` to only warn.")
+    )]
+    AdvisoriesFound {
+        version: CompactString,
+        advisories: Box<str>,
+    },
+
+    /// The smoke test command in the settings is invalid.
+    ///
+    /// - Code: `binstall::smoke_test_command`
+    /// - Exit: 106
+    #[error("invalid smoke test command: {0}")]
+    #[diagnostic(
+        severity(error),
+        code(binstall::smoke_test_command),
+        help("Check `command` under `[smoke_test]` in the settings, it supports the keys `bin`, `name` and `version`.")
+    )]
+    InvalidSmokeTestCommand(CompactString),
+
+    /// A binary is renamed with `--rename` or `renames` in the settings,
+    /// but the crate has no such binary, or binaries would be installed
+    /// under the same name.
+    ///
+    /// - Code: `binstall::rename`
+    /// - Exit: 107
+    #[error("invalid rename: {0}")]
+    #[diagnostic(
+        severity(error),
+        code(binstall::rename),
+        help("Check `--rename` and `renames` in the settings.")
+    )]
+    InvalidBinRename(CompactString),
+
     /// A wrapped error providing the context of which crate the error is about.
     #[error(transparent)]
     #[diagnostic(transparent)]
@@ -479,6 +579,13 @@ impl BinstallError {
             GitError(_) => 98,
             LoadManifestFromWSError(_) => 99,
             CargoInstallDoesNotSupportInstallPath => 100,
+            NoBackup(_) => 101,
+            VersionNotInstalled { .. } => 102,
+            SmokeTestFailed { .. } => 103,
+            LicenseNotAllowed { .. } => 104,
+            AdvisoriesFound { .. } => 105,
+            InvalidSmokeTestCommand(_) => 106,
+            InvalidBinRename(_) => 107,
             CrateContext(context) => context.err.exit_number(),
             Errors(errors) => (errors.0)[0].err.exit_number(),
         };
@@ -15,6 +15,14 @@ impl Deref for TargetTriple {
     }
 }
 
+impl TargetTriple {
+    /// Set the x86-64 psABI microarchitecture level, which is ignored if the
+    /// target is not x86_64.
+    pub fn with_cpu_level(self, cpu_level: Option<u8>) -> Self {
+        Self(self.0.with_cpu_level(cpu_level))
+    }
+}
+
 impl FromStr for TargetTriple {
     type Err = BinstallError;
 
@@ -34,6 +42,9 @@ impl leon::Values for TargetTriple {
             "target-arch" => Some(self.arch.into_str()),
             "target-libc" => Some(self.env.into_str()),
             "target-vendor" => Some(Cow::Borrowed(self.vendor.as_str())),
+            "target-cpu-level" => self
+                .cpu_level
+                .map(|level| CfgOption::CpuLevel(level).value()),
 
             _ => None,
         }
@@ -63,6 +74,15 @@ mod tests {
         assert_eq!(triple.get_value("os-name").as_deref(), Some("windows"));
     }
 
+    #[test]
+    fn test_target_cpu_level() {
+        let triple: TargetTriple = "x86_64-unknown-linux-gnu".parse().unwrap();
+        assert_eq!(triple.get_value("target-cpu-level"), None);
+
+        let triple = triple.with_cpu_level(Some(3));
+        assert_eq!(triple.get_value("target-cpu-level").as_deref(), Some("v3"));
+    }
+
     #[test]
     fn test_existing_keys_unchanged() {
         let triple: TargetTriple = "x86_64-unknown-linux-gnu".parse().unwrap();
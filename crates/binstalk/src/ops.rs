@@ -1,6 +1,6 @@
 //! Concrete Binstall operations.
 
-use std::{path::PathBuf, sync::Arc, time::Duration};
+use std::{collections::BTreeMap, path::PathBuf, sync::Arc, time::Duration};
 
 use compact_str::CompactString;
 
@@ -11,11 +11,14 @@ use crate::{
         lazy_gh_api_client::LazyGhApiClient, remote::Client,
     },
     manifests::cargo_toml_binstall::PkgOverride,
-    registry::ResolvedRegistry,
+    registry::{ResolvedRegistry, VersionFilter},
     DesiredTargets,
 };
 
+pub mod advisories;
+pub mod license_policy;
 pub mod resolve;
+pub mod smoke_test;
 
 pub type Resolver =
     fn(Client, GhApiClient, Arc<Data>, Arc<TargetDataErased>, SignaturePolicy) -> Arc<dyn Fetcher>;
@@ -36,6 +39,29 @@ pub struct Options {
     pub quiet: bool,
     pub locked: bool,
     pub no_track: bool,
+    /// If provided, run it against every installed binary.
+    pub smoke_test: Option<smoke_test::SmokeTest>,
+    /// Reject binaries linked against shared libraries missing on the host,
+    /// instead of only warning about them.
+    pub strict_shared_libs: bool,
+    /// If provided, reject crates whose license is not allowed by it.
+    pub license_policy: Option<license_policy::LicensePolicy>,
+    /// If provided, check crates against it and warn about the advisories
+    /// found.
+    pub advisory_db: Option<advisories::AdvisoryDb>,
+    /// Refuse to install crates affected by advisories, instead of only
+    /// warning about them.
+    pub deny_advisories: bool,
+    /// Restrictions on the versions matched in the registry, e.g. whether
+    /// yanked versions can be installed.
+    pub version_filter: VersionFilter,
+    /// Runtime to create launchers of WASI modules for, e.g. `wasmtime`.
+    ///
+    /// If not provided, the modules are executed directly if supported,
+    /// otherwise a known runtime is searched in `PATH`.
+    pub wasi_runtime: Option<CompactString>,
+    /// Extra arguments passed to the WASI runtime before the module.
+    pub wasi_runtime_flags: Vec<CompactString>,
 
     pub cargo_toml_fetch_override: Option<CargoTomlFetchOverride>,
     pub cli_overrides: PkgOverride,
@@ -46,10 +72,19 @@ pub struct Options {
 
     /// If provided, the names are sorted.
     pub bins: Option<Vec<CompactString>>,
+    /// Binaries to install under a different name, keyed by crate name,
+    /// then by the original binary name.
+    pub bin_renames: BTreeMap<CompactString, BTreeMap<CompactString, CompactString>>,
 
     pub temp_dir: PathBuf,
     pub install_path: PathBuf,
     pub has_overriden_install_path: bool,
+    /// Root to install extra files, e.g. man pages, under, usually the
+    /// cargo root.
+    ///
+    /// If not provided, extra files are not installed, since they could not
+    /// be tracked.
+    pub extra_files_root: Option<PathBuf>,
     pub cargo_root: Option<PathBuf>,
     pub cargo_install_registry: Option<CompactString>,
     pub cargo_install_index: Option<CompactString>,
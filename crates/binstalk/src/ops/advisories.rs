@@ -0,0 +1,289 @@
+//! Check of crates against a [RustSec advisory database].
+//!
+//! [RustSec advisory database]: https://github.com/rustsec/advisory-db
+
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+};
+
+use compact_str::CompactString;
+use semver::{Version, VersionReq};
+use serde::Deserialize;
+use tokio::{sync::OnceCell, task::spawn_blocking};
+use tracing::warn;
+
+use crate::errors::BinstallError;
+
+#[derive(Debug, Clone)]
+pub enum AdvisoryDbSource {
+    /// A local checkout of the advisory database.
+    Path(PathBuf),
+    /// The advisory database is cloned from the git repository.
+    #[cfg(feature = "git")]
+    Git(crate::registry::GitUrl),
+}
+
+/// An advisory affecting a crate version.
+#[derive(Debug, Clone)]
+pub struct Advisory {
+    pub id: CompactString,
+    pub title: CompactString,
+    /// The crate is unmaintained, rather than having a vulnerability.
+    pub unmaintained: bool,
+}
+
+impl fmt::Display for Advisory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.id)?;
+        if self.unmaintained {
+            write!(f, " (unmaintained)")?;
+        }
+        if !self.title.is_empty() {
+            write!(f, ": {}", self.title)?;
+        }
+        Ok(())
+    }
+}
+
+/// Advisory database, fetched on first use.
+#[derive(Debug)]
+pub struct AdvisoryDb {
+    source: AdvisoryDbSource,
+    dir: OnceCell<(PathBuf, Option<tempfile::TempDir>)>,
+}
+
+impl AdvisoryDb {
+    pub fn new(source: AdvisoryDbSource) -> Self {
+        Self {
+            source,
+            dir: OnceCell::new(),
+        }
+    }
+
+    async fn dir(&self) -> Result<&Path, BinstallError> {
+        let (dir, _temp_dir) = self
+            .dir
+            .get_or_try_init(|| async {
+                match &self.source {
+                    AdvisoryDbSource::Path(path) => Ok::<_, BinstallError>((path.clone(), None)),
+                    #[cfg(feature = "git")]
+                    AdvisoryDbSource::Git(url) => {
+                        use tracing::info;
+
+                        use crate::helpers::git::{GitCancellationToken, Repository};
+
+                        info!("Fetching advisory database from {url}");
+
+                        let cancellation_token = GitCancellationToken::default();
+                        // Cancel git operation if the future is cancelled (dropped).
+                        let cancel_on_drop = cancellation_token.clone().cancel_on_drop();
+
+                        let url = url.clone();
+                        let temp_dir = spawn_blocking(move || {
+                            let dir = tempfile::TempDir::new()?;
+                            Repository::shallow_clone(url, dir.as_ref(), Some(cancellation_token))?;
+                            Ok::<_, BinstallError>(dir)
+                        })
+                        .await??;
+
+                        cancel_on_drop.disarm();
+
+                        Ok((temp_dir.path().to_path_buf(), Some(temp_dir)))
+                    }
+                }
+            })
+            .await?;
+
+        Ok(dir)
+    }
+
+    /// Return the advisories affecting `version` of crate `name`, including
+    /// those marking the crate as unmaintained.
+    ///
+    /// Advisories of the crate that fail to parse are warned about, or
+    /// returned as an error if `deny` is set.
+    pub async fn check(
+        &self,
+        name: &str,
+        version: &Version,
+        deny: bool,
+    ) -> Result<Vec<Advisory>, BinstallError> {
+        let dir = self.dir().await?.join("crates").join(name);
+        let version = version.clone();
+
+        spawn_blocking(move || check_dir(&dir, &version, deny))
+            .await?
+            .map_err(BinstallError::from)
+    }
+}
+
+fn check_dir(dir: &Path, version: &Version, deny: bool) -> io::Result<Vec<Advisory>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(err) => return Err(err),
+    };
+
+    let mut advisories = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension() != Some("md".as_ref()) {
+            continue;
+        }
+
+        match parse_advisory(&fs::read_to_string(&path)?, version) {
+            Ok(Some(advisory)) => advisories.push(advisory),
+            Ok(None) => (),
+            Err(err) if deny => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Failed to parse advisory {}: {err}", path.display()),
+                ))
+            }
+            Err(err) => warn!("Failed to parse advisory {}: {err}", path.display()),
+        }
+    }
+    advisories.sort_unstable_by(|x, y| x.id.cmp(&y.id));
+
+    Ok(advisories)
+}
+
+#[derive(Deserialize)]
+struct FrontMatter {
+    advisory: AdvisoryMeta,
+    #[serde(default)]
+    versions: Versions,
+}
+
+#[derive(Deserialize)]
+struct AdvisoryMeta {
+    id: CompactString,
+    #[serde(default)]
+    informational: Option<CompactString>,
+    #[serde(default)]
+    withdrawn: Option<toml::Value>,
+}
+
+#[derive(Default, Deserialize)]
+struct Versions {
+    #[serde(default)]
+    patched: Vec<VersionReq>,
+    #[serde(default)]
+    unaffected: Vec<VersionReq>,
+}
+
+/// Parse an advisory in markdown with TOML front matter, returning it if
+/// it affects `version` and is a vulnerability or an unmaintained notice.
+fn parse_advisory(content: &str, version: &Version) -> Result<Option<Advisory>, toml::de::Error> {
+    let content = content.trim_start();
+    let Some((front_matter, body)) = content
+        .strip_prefix("```toml")
+        .and_then(|content| content.split_once("\n```"))
+    else {
+        return Ok(None);
+    };
+
+    let FrontMatter { advisory, versions } = toml::from_str(front_matter)?;
+
+    let unmaintained = match advisory.informational.as_deref() {
+        None => false,
+        Some("unmaintained") => true,
+        Some(_) => return Ok(None),
+    };
+
+    if advisory.withdrawn.is_some()
+        || versions
+            .patched
+            .iter()
+            .chain(&versions.unaffected)
+            .any(|req| req.matches(version))
+    {
+        return Ok(None);
+    }
+
+    let title = body
+        .lines()
+        .find_map(|line| line.strip_prefix("# "))
+        .unwrap_or_default()
+        .trim();
+
+    Ok(Some(Advisory {
+        id: advisory.id,
+        title: title.into(),
+        unmaintained,
+    }))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const ADVISORY: &str = r#"```toml
+[advisory]
+id = "RUSTSEC-2099-0001"
+package = "foo"
+date = "2099-01-01"
+
+[versions]
+patched = [">= 1.2.3"]
+unaffected = ["< 1.0.0"]
+```
+
+# Remote code execution in foo
+
+Details.
+"#;
+
+    #[test]
+    fn parse() {
+        let advisory = parse_advisory(ADVISORY, &Version::new(1, 2, 0))
+            .unwrap()
+            .unwrap();
+        assert_eq!(advisory.id, "RUSTSEC-2099-0001");
+        assert_eq!(advisory.title, "Remote code execution in foo");
+        assert!(!advisory.unmaintained);
+
+        for version in [Version::new(1, 2, 3), Version::new(0, 9, 0)] {
+            assert!(parse_advisory(ADVISORY, &version).unwrap().is_none());
+        }
+
+        let unmaintained = ADVISORY.replace("date = ", "informational = \"unmaintained\"\ndate = ");
+        assert!(
+            parse_advisory(&unmaintained, &Version::new(1, 2, 0))
+                .unwrap()
+                .unwrap()
+                .unmaintained
+        );
+
+        let unsound = ADVISORY.replace("date = ", "informational = \"unsound\"\ndate = ");
+        assert!(parse_advisory(&unsound, &Version::new(1, 2, 0))
+            .unwrap()
+            .is_none());
+
+        let withdrawn = ADVISORY.replace("date = ", "withdrawn = \"2099-01-02\"\ndate = ");
+        assert!(parse_advisory(&withdrawn, &Version::new(1, 2, 0))
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn invalid_advisory() {
+        let dir = tempfile::TempDir::new().unwrap();
+        fs::write(dir.path().join("RUSTSEC-2099-0001.md"), ADVISORY).unwrap();
+        fs::write(
+            dir.path().join("RUSTSEC-2099-0002.md"),
+            ADVISORY.replace("[advisory]", "[advisory"),
+        )
+        .unwrap();
+
+        let version = Version::new(1, 2, 0);
+
+        let advisories = check_dir(dir.path(), &version, false).unwrap();
+        assert_eq!(advisories.len(), 1);
+        assert_eq!(advisories[0].id, "RUSTSEC-2099-0001");
+
+        let err = check_dir(dir.path(), &version, true).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
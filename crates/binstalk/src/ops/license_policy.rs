@@ -0,0 +1,91 @@
+//! Policy on the licenses of crates to install.
+
+use compact_str::{format_compact, CompactString};
+use spdx::{Expression, LicenseReq, Licensee, ParseError, ParseMode};
+
+use crate::errors::BinstallError;
+
+#[derive(Debug, Clone)]
+pub struct LicensePolicy {
+    allowed: Vec<Licensee>,
+    denied: Vec<Licensee>,
+}
+
+impl LicensePolicy {
+    /// * `allowed` - licenses allowed, or any license not denied if empty.
+    /// * `denied` - licenses denied, taking precedence over `allowed`.
+    ///
+    /// Each license is an SPDX license identifier, optionally with an
+    /// exception, e.g. `Apache-2.0 WITH LLVM-exception`.
+    pub fn new<S: AsRef<str>>(allowed: &[S], denied: &[S]) -> Result<Self, ParseError> {
+        let parse = |licenses: &[S]| {
+            licenses
+                .iter()
+                .map(|license| Licensee::parse(license.as_ref()))
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        Ok(Self {
+            allowed: parse(allowed)?,
+            denied: parse(denied)?,
+        })
+    }
+
+    fn accepts(&self, req: &LicenseReq) -> bool {
+        !self.denied.iter().any(|licensee| licensee.satisfies(req))
+            && (self.allowed.is_empty()
+                || self.allowed.iter().any(|licensee| licensee.satisfies(req)))
+    }
+
+    /// Return `Ok` if the crate can be used under a license accepted by
+    /// the policy, given its `license` field.
+    pub(crate) fn check(&self, license: Option<&str>) -> Result<(), BinstallError> {
+        let reject = |reason: CompactString| BinstallError::LicenseNotAllowed {
+            license: license.map(Into::into),
+            reason,
+        };
+
+        let Some(license) = license else {
+            return if self.allowed.is_empty() {
+                Ok(())
+            } else {
+                Err(reject("the crate does not specify an SPDX license".into()))
+            };
+        };
+
+        // Use lax mode to accept the deprecated `/` separator and
+        // imprecise license names still used by many crates.
+        let expr = Expression::parse_mode(license, ParseMode::LAX)
+            .map_err(|err| reject(format_compact!("invalid SPDX expression: {err}")))?;
+
+        if expr.evaluate(|req| self.accepts(req)) {
+            Ok(())
+        } else {
+            Err(reject("no license is allowed by the license policy".into()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn check() {
+        let policy = LicensePolicy::new(&["MIT", "Apache-2.0"], &[]).unwrap();
+        policy.check(Some("MIT OR Apache-2.0")).unwrap();
+        policy.check(Some("MIT/Apache-2.0")).unwrap();
+        policy.check(Some("GPL-3.0-only OR MIT")).unwrap();
+        policy.check(Some("GPL-3.0-only AND MIT")).unwrap_err();
+        policy.check(Some("GPL-3.0-only")).unwrap_err();
+        policy.check(None).unwrap_err();
+
+        let policy = LicensePolicy::new(&[], &["GPL-3.0"]).unwrap();
+        policy.check(Some("MIT")).unwrap();
+        policy.check(None).unwrap();
+        policy.check(Some("GPL-3.0-or-later")).unwrap_err();
+        policy.check(Some("GPL-3.0-only OR Apache-2.0")).unwrap();
+
+        LicensePolicy::new(&["not a license"], &[]).unwrap_err();
+    }
+}
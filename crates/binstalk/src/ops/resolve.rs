@@ -5,11 +5,11 @@ use binstalk_types::{
     cargo_toml_binstall::Strategy,
     crate_info::{CrateSource, SourceType},
 };
-use compact_str::{CompactString, ToCompactString};
+use compact_str::{format_compact, CompactString, ToCompactString};
 use itertools::Itertools;
 use leon::Template;
 use maybe_owned::MaybeOwned;
-use semver::{Comparator, Op as ComparatorOp, Version, VersionReq};
+use semver::{Version, VersionReq};
 use tokio::{task::spawn_blocking, time::timeout};
 use tracing::{debug, error, info, instrument, warn};
 use url::Url;
@@ -24,7 +24,8 @@ use crate::{
         tasks::AutoAbortJoinHandle,
     },
     manifests::cargo_toml_binstall::{Meta, PkgMeta, PkgOverrides},
-    ops::{CargoTomlFetchOverride, Options},
+    ops::{advisories::Advisory, CargoTomlFetchOverride, Options},
+    registry::exact_version_req,
 };
 
 mod crate_name;
@@ -64,7 +65,7 @@ async fn resolve_inner(
 
     let version_req_str = version_req.to_compact_string();
 
-    let Some(package_info) = PackageInfo::resolve(
+    let Some(mut package_info) = PackageInfo::resolve(
         &opts,
         crate_name.name,
         curr_version,
@@ -76,6 +77,40 @@ async fn resolve_inner(
         return Ok(Resolution::AlreadyUpToDate);
     };
 
+    if opts.version_filter.allow_yanked && exact_version_req(&version_req).is_none() {
+        warn!(
+            "--allow-yanked is ignored for {} since no exact version `=x.y.z` is requested",
+            package_info.name
+        );
+    }
+
+    if let Some(advisory_db) = &opts.advisory_db {
+        package_info.advisories = match advisory_db
+            .check(
+                &package_info.name,
+                &package_info.version,
+                opts.deny_advisories,
+            )
+            .await
+        {
+            Ok(advisories) => advisories,
+            Err(err) if !opts.deny_advisories => {
+                warn!("Failed to check advisories: {err}");
+                Vec::new()
+            }
+            Err(err) => return Err(err),
+        };
+
+        if opts.deny_advisories && !package_info.advisories.is_empty() {
+            return Err(BinstallError::AdvisoriesFound {
+                version: package_info.version_str,
+                advisories: package_info.advisories.iter().join(", ").into(),
+            });
+        }
+    }
+
+    let cpu_level = detect_targets::detect_x86_64_level();
+
     let desired_targets = opts
         .desired_targets
         .get()
@@ -84,7 +119,7 @@ async fn resolve_inner(
         .map(|target| {
             debug!("Building metadata for target: {target}");
 
-            let target_related_info = TargetTriple::from_str(target)?;
+            let target_related_info = TargetTriple::from_str(target)?.with_cpu_level(cpu_level);
 
             let cfgs = target_related_info.cfgs();
             let meta = package_info.meta.merge_overrides(
@@ -188,13 +223,11 @@ async fn resolve_inner(
                         fetcher.as_ref(),
                         &bin_path,
                         &package_info,
-                        &opts.install_path,
-                        opts.no_symlinks,
-                        &opts.bins,
+                        &opts,
                     )
                     .await
                     {
-                        Ok(bin_files) => {
+                        Ok((bin_files, extra_files)) => {
                             if !bin_files.is_empty() {
                                 if !opts.disable_telemetry {
                                     fetcher.clone().report_to_upstream();
@@ -205,7 +238,11 @@ async fn resolve_inner(
                                     name: package_info.name,
                                     version_req: version_req_str,
                                     source: package_info.source,
+                                    license: package_info.license,
+                                    advisories: package_info.advisories,
+                                    yanked: package_info.yanked,
                                     bin_files,
+                                    extra_files,
                                 })));
                             } else {
                                 warn!(
@@ -276,6 +313,9 @@ async fn resolve_inner(
     Ok(Resolution::InstallFromSource(ResolutionSource {
         name: package_info.name,
         version: package_info.version_str,
+        license: package_info.license,
+        advisories: package_info.advisories,
+        yanked: package_info.yanked,
     }))
 }
 
@@ -283,14 +323,14 @@ async fn resolve_inner(
 ///
 /// Can return empty Vec if all `BinFile` is optional and does not exist
 /// in the archive downloaded.
+///
+/// Also return the extra files found in the archive downloaded.
 async fn download_extract_and_verify(
     fetcher: &dyn Fetcher,
     bin_path: &Path,
     package_info: &PackageInfo,
-    install_path: &Path,
-    no_symlinks: bool,
-    bins: &Option<Vec<CompactString>>,
-) -> Result<Vec<bins::BinFile>, BinstallError> {
+    opts: &Options,
+) -> Result<(Vec<bins::BinFile>, Vec<bins::ExtraFile>), BinstallError> {
     // Download and extract it.
     // If that fails, then ignore this fetcher.
     let extracted_files = fetcher.fetch_and_extract(bin_path).await?;
@@ -299,26 +339,36 @@ async fn download_extract_and_verify(
     // Build final metadata
     let meta = fetcher.target_meta();
 
+    let extra_files = collect_extra_files(
+        fetcher,
+        package_info,
+        meta.clone(),
+        bin_path,
+        opts.extra_files_root.as_deref(),
+        &extracted_files,
+    );
+
     // Verify that all non-optional bin_files exist
     let bin_files = collect_bin_files(
         fetcher,
         package_info,
         meta,
         bin_path,
-        install_path,
-        no_symlinks,
+        &opts.install_path,
+        opts.no_symlinks,
         &extracted_files,
     )?;
 
     let name = &package_info.name;
 
-    package_info
+    let bin_files = package_info
         .binaries
         .iter()
         .zip(bin_files)
         .filter_map(|(bin, bin_file)| {
             // skip binaries that were not requested by user
-            if bins
+            if opts
+                .bins
                 .as_ref()
                 .is_some_and(|bins| !bins.iter().any(|b| b == bin.name))
             {
@@ -353,8 +403,114 @@ But since it requires features {features}, this bin is ignored."
                 }
             }
         })
-        .collect::<Result<Vec<bins::BinFile>, bins::Error>>()
-        .map_err(BinstallError::from)
+        .collect::<Result<Vec<bins::BinFile>, bins::Error>>()?;
+
+    // Reject binaries built for another target, e.g. mislabelled assets,
+    // or requiring a newer glibc than the host's, so that the next fetcher
+    // (e.g. for the musl target) is tried.
+    let target = fetcher.target();
+    // The host's glibc and shared libraries can only be checked against if
+    // the binaries are meant to run natively on the host, i.e. the targets
+    // are detected and this is not a foreign target emulated through
+    // `binfmt_misc`.
+    let runs_natively = opts
+        .desired_targets
+        .detection()
+        .await
+        .is_some_and(|detection| target_arch(&detection.host_target) == target_arch(target));
+    let host_glibc_version = if runs_natively && target.contains("-linux-gnu") {
+        opts.desired_targets.host_glibc_version().await
+    } else {
+        None
+    };
+    let check_shared_libs =
+        cfg!(target_os = "linux") && runs_natively && target.contains("-linux-");
+
+    let strict_shared_libs = opts.strict_shared_libs;
+
+    // The checks read the binaries and search for shared libraries on disk.
+    let bin_files = spawn_blocking({
+        let target = target.to_owned();
+        move || {
+            for bin_file in &bin_files {
+                bin_file.check_binary_format(&target)?;
+                if let Some(host_glibc_version) = host_glibc_version {
+                    bin_file.check_glibc_version(host_glibc_version)?;
+                }
+                if check_shared_libs {
+                    match bin_file.check_shared_libs() {
+                        Err(err @ bins::Error::MissingSharedLibs { .. }) if !strict_shared_libs => {
+                            warn!("{err}");
+                        }
+                        res => res?,
+                    }
+                }
+            }
+            Ok::<_, bins::Error>(bin_files)
+        }
+    })
+    .await??;
+
+    Ok((bin_files, extra_files))
+}
+
+fn target_arch(target: &str) -> &str {
+    target.split('-').next().unwrap_or(target)
+}
+
+/// Extra files that are not found or misconfigured are skipped with a
+/// warning, since they are not needed to run the binaries.
+///
+/// Return an empty Vec if `root` is `None`.
+fn collect_extra_files(
+    fetcher: &dyn Fetcher,
+    package_info: &PackageInfo,
+    meta: PkgMeta,
+    bin_path: &Path,
+    root: Option<&Path>,
+    extracted_files: &ExtractedFiles,
+) -> Vec<bins::ExtraFile> {
+    let extra_files = meta.extra_files.clone();
+    if extra_files.is_empty() {
+        return Vec::new();
+    }
+    let Some(root) = root else {
+        warn!(
+            "Extra files of {} are not installed with a custom install path",
+            package_info.name
+        );
+        return Vec::new();
+    };
+    let data = bins::Data {
+        name: &package_info.name,
+        target: fetcher.target(),
+        version: &package_info.version_str,
+        repo: package_info.repo.as_deref(),
+        meta,
+        bin_path,
+        install_path: root,
+        target_related_info: &fetcher.target_data().target_related_info,
+    };
+
+    extra_files
+        .iter()
+        .filter_map(|extra_file| {
+            let res = bins::ExtraFile::new(&data, extra_file, root).and_then(|file| {
+                let Some(file) = file else {
+                    return Ok(None);
+                };
+                file.check_source_exists(&mut |p| extracted_files.has_file(p))?;
+                Ok(Some(file))
+            });
+            res.unwrap_or_else(|err| {
+                warn!(
+                    "When resolving {} extra file {}: {err}, this file is ignored",
+                    package_info.name, extra_file.source
+                );
+                None
+            })
+        })
+        .collect()
 }
 
 fn collect_bin_files(
@@ -394,7 +550,15 @@ fn collect_bin_files(
     let bin_files = package_info
         .binaries
         .iter()
-        .map(|bin| bins::BinFile::new(&bin_data, bin.name.as_str(), &template, no_symlinks))
+        .map(|bin| {
+            bins::BinFile::new(
+                &bin_data,
+                bin.name.as_str(),
+                bin.install_name.as_deref(),
+                &template,
+                no_symlinks,
+            )
+        })
         .collect::<Result<Vec<_>, bins::Error>>()?;
 
     let mut source_set = BTreeSet::new();
@@ -418,12 +582,18 @@ struct PackageInfo {
     source: CrateSource,
     version: Version,
     repo: Option<String>,
+    license: Option<CompactString>,
+    /// Advisories affecting the version, if checked.
+    advisories: Vec<Advisory>,
+    yanked: bool,
     overrides: PkgOverrides,
 }
 
 struct Bin {
     name: String,
     required_features: Vec<String>,
+    /// Install the binary under this name if provided.
+    install_name: Option<CompactString>,
 }
 
 impl PackageInfo {
@@ -437,6 +607,8 @@ impl PackageInfo {
     ) -> Result<Option<Self>, BinstallError> {
         use CargoTomlFetchOverride::*;
 
+        let mut yanked = false;
+
         // Fetch crate via crates.io, git, or use a local manifest path
         let (manifest, source) = match opts.cargo_toml_fetch_override.as_ref() {
             Some(Path(manifest_path)) => (
@@ -494,14 +666,18 @@ impl PackageInfo {
                     },
                 )
             }
-            None => (
-                Box::pin(
-                    opts.registry
-                        .fetch_crate_matched(client, &name, version_req),
-                )
-                .await?,
-                opts.registry.crate_source()?,
-            ),
+            None => {
+                let matched = Box::pin(opts.registry.fetch_crate_matched(
+                    client,
+                    &name,
+                    version_req,
+                    &opts.version_filter,
+                ))
+                .await?;
+                yanked = matched.yanked;
+
+                (matched.manifest, opts.registry.crate_source()?)
+            }
         };
 
         let Some(mut package) = manifest.package else {
@@ -528,6 +704,13 @@ impl PackageInfo {
             }
         }
 
+        let license = package.license().map(CompactString::from);
+        if let Some(license_policy) = &opts.license_policy {
+            license_policy.check(license.as_deref())?;
+        }
+
+        let bin_renames = opts.bin_renames.get(&name);
+
         let (mut meta, binaries): (_, Vec<Bin>) = (
             package
                 .metadata
@@ -539,6 +722,9 @@ impl PackageInfo {
                 .into_iter()
                 .filter_map(|p| {
                     p.name.map(|name| Bin {
+                        install_name: bin_renames
+                            .and_then(|renames| renames.get(name.as_str()))
+                            .cloned(),
                         name,
                         required_features: p.required_features,
                     })
@@ -546,6 +732,27 @@ impl PackageInfo {
                 .collect(),
         );
 
+        if let Some(bin_renames) = bin_renames {
+            if let Some(from) = bin_renames
+                .keys()
+                .find(|from| !binaries.iter().any(|bin| bin.name == from.as_str()))
+            {
+                return Err(BinstallError::InvalidBinRename(format_compact!(
+                    "{name} has no binary {from}"
+                )));
+            }
+
+            let mut install_names = BTreeSet::new();
+            for bin in &binaries {
+                let install_name = bin.install_name.as_deref().unwrap_or(&bin.name);
+                if !install_names.insert(install_name) {
+                    return Err(BinstallError::InvalidBinRename(format_compact!(
+                        "more than one binary of {name} would be installed as {install_name}"
+                    )));
+                }
+            }
+        }
+
         // Check binaries
         if binaries.is_empty() {
             Err(BinstallError::UnspecifiedBinaries)
@@ -559,6 +766,9 @@ impl PackageInfo {
                 version_str: new_version_str,
                 version: new_version,
                 repo: package.repository().map(ToString::to_string),
+                license,
+                advisories: Vec::new(),
+                yanked,
             }))
         }
     }
@@ -581,25 +791,6 @@ fn parse_package_version(
     Ok((version_str, version))
 }
 
-fn exact_version_req(version_req: &VersionReq) -> Option<Version> {
-    match version_req.comparators.as_slice() {
-        [Comparator {
-            op: ComparatorOp::Exact,
-            major,
-            minor: Some(minor),
-            patch: Some(patch),
-            pre,
-        }] => Some(Version {
-            major: *major,
-            minor: *minor,
-            patch: *patch,
-            pre: pre.clone(),
-            build: Default::default(),
-        }),
-        _ => None,
-    }
-}
-
 /// Load binstall metadata from the crate `Cargo.toml` at the provided path
 ///
 /// This is a blocking function.
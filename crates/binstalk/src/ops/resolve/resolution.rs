@@ -1,6 +1,6 @@
 use std::{borrow::Cow, env, ffi::OsStr, fmt, iter, path::Path, sync::Arc};
 
-use binstalk_bins::BinFile;
+use binstalk_bins::{BinFile, Transaction, WasiLauncher};
 use command_group::AsyncCommandGroup;
 use compact_str::{CompactString, ToCompactString};
 use either::Either;
@@ -14,7 +14,7 @@ use crate::{
     errors::BinstallError,
     fetchers::Fetcher,
     manifests::crate_info::{CrateInfo, CrateSource},
-    ops::Options,
+    ops::{advisories::Advisory, Options},
 };
 
 pub struct ResolutionFetch {
@@ -23,12 +23,25 @@ pub struct ResolutionFetch {
     pub name: CompactString,
     pub version_req: CompactString,
     pub bin_files: Vec<bins::BinFile>,
+    pub extra_files: Vec<bins::ExtraFile>,
     pub source: CrateSource,
+    /// SPDX license expression from the crate manifest.
+    pub license: Option<CompactString>,
+    /// Advisories affecting the version.
+    pub advisories: Vec<Advisory>,
+    /// The version has been yanked from the registry.
+    pub yanked: bool,
 }
 
 pub struct ResolutionSource {
     pub name: CompactString,
     pub version: CompactString,
+    /// SPDX license expression from the crate manifest.
+    pub license: Option<CompactString>,
+    /// Advisories affecting the version.
+    pub advisories: Vec<Advisory>,
+    /// The version has been yanked from the registry.
+    pub yanked: bool,
 }
 
 pub enum Resolution {
@@ -52,14 +65,25 @@ impl Resolution {
 }
 
 impl ResolutionFetch {
-    pub fn install(self, opts: &Options) -> Result<CrateInfo, BinstallError> {
+    /// Install the binaries, recording every replaced or created file in
+    /// `transaction` so that the caller can revert them if the installation
+    /// of this or any other crate fails.
+    pub fn install(
+        self,
+        opts: &Options,
+        transaction: &mut Transaction,
+    ) -> Result<CrateInfo, BinstallError> {
         let crate_name = self.name.clone();
-        self.install_inner(opts)
+        self.install_inner(opts, transaction)
             .map_err(|err| err.crate_context(crate_name))
     }
 
-    fn install_inner(self, opts: &Options) -> Result<CrateInfo, BinstallError> {
-        type InstallFp = fn(&bins::BinFile) -> Result<(), bins::Error>;
+    fn install_inner(
+        self,
+        opts: &Options,
+        transaction: &mut Transaction,
+    ) -> Result<CrateInfo, BinstallError> {
+        type InstallFp = fn(&bins::BinFile, &mut Transaction) -> Result<(), bins::Error>;
 
         let (install_bin, install_link): (InstallFp, InstallFp) = match (opts.no_track, opts.force)
         {
@@ -70,42 +94,119 @@ impl ResolutionFetch {
             ),
         };
 
+        let wasi_launcher = if bins::is_wasi_target(self.fetcher.target()) {
+            let launcher = resolve_wasi_launcher(opts);
+            if launcher.is_none() {
+                warn!(
+                    "No WASI runtime is found, {} will be installed as WASI modules without launchers. \
+Set `wasi.runtime` in the settings to the runtime to use.",
+                    self.name
+                );
+            }
+            launcher
+        } else {
+            None
+        };
+
         info!("Installing binaries...");
         for file in &self.bin_files {
-            install_bin(file)?;
+            install_bin(file, transaction)?;
         }
 
         // Generate symlinks
         if !opts.no_symlinks {
             for file in &self.bin_files {
-                install_link(file)?;
+                install_link(file, transaction)?;
+            }
+        }
+
+        if !self.extra_files.is_empty() {
+            info!("Installing extra files...");
+            for file in &self.extra_files {
+                if opts.no_track && !opts.force {
+                    file.install_noclobber(transaction)?;
+                } else {
+                    file.install(transaction)?;
+                }
+            }
+        }
+
+        if let Some(launcher) = &wasi_launcher {
+            info!("Creating launchers for WASI modules...");
+            for file in &self.bin_files {
+                if opts.no_track && !opts.force {
+                    file.install_wasi_launcher_noclobber(launcher, transaction)?;
+                } else {
+                    file.install_wasi_launcher(launcher, transaction)?;
+                }
+            }
+        }
+
+        if let Some(smoke_test) = &opts.smoke_test {
+            info!("Running smoke tests...");
+            for file in &self.bin_files {
+                let path = wasi_launcher
+                    .as_ref()
+                    .and_then(|_| file.wasi_launcher_path())
+                    .unwrap_or_else(|| file.dest.clone());
+                smoke_test.run(&path, &file.install_name, &self.new_version, &opts.temp_dir)?;
             }
         }
 
         Ok(CrateInfo {
+            bin_renames: opts
+                .bin_renames
+                .get(&self.name)
+                .cloned()
+                .unwrap_or_default(),
             name: self.name,
             version_req: self.version_req,
+            installed_versions: if opts.no_symlinks {
+                Vec::new()
+            } else {
+                vec![self.new_version.clone()]
+            },
             current_version: self.new_version,
             source: self.source,
+            license: self.license,
+            yanked: self.yanked,
             target: self.fetcher.target().to_compact_string(),
-            bins: Self::resolve_bins(&opts.bins, self.bin_files),
+            bins: Self::resolve_bins(&opts.bins, self.bin_files, wasi_launcher.is_some()),
+            wasi_runtime: wasi_launcher.map(|launcher| launcher.runtime_name().into()),
+            extra_files: self
+                .extra_files
+                .into_iter()
+                .map(|file| file.rel_dest)
+                .collect(),
         })
     }
 
+    /// Return the names of the installed binaries, including the launchers
+    /// of WASI modules if `with_wasi_launchers` is true, so that they are
+    /// removed by `cargo uninstall` and backed up along with the modules.
     fn resolve_bins(
         user_specified_bins: &Option<Vec<CompactString>>,
         crate_bin_files: Vec<BinFile>,
+        with_wasi_launchers: bool,
     ) -> Vec<CompactString> {
         // We need to filter crate_bin_files by user_specified_bins in case the prebuilt doesn't
         // have featured-gated (optional) binary (gated behind feature).
         crate_bin_files
             .into_iter()
-            .map(|bin| bin.base_name)
-            .filter(|bin_name| {
+            .filter(|bin| {
                 user_specified_bins
                     .as_ref()
-                    .map_or(true, |bins| bins.binary_search(bin_name).is_ok())
+                    .map_or(true, |bins| bins.binary_search(&bin.base_name).is_ok())
+            })
+            .flat_map(|bin| {
+                let launcher = with_wasi_launchers
+                    .then(|| bin.wasi_launcher_path())
+                    .flatten()
+                    .and_then(|path| Some(path.file_name()?.to_str()?.to_compact_string()));
+
+                [Some(bin.install_name), launcher]
             })
+            .flatten()
             .collect()
     }
 
@@ -131,6 +232,10 @@ impl ResolutionFetch {
             fetcher.source_name()
         );
 
+        info!("License: {}", DisplayLicense(self.license.as_deref()));
+        print_advisories(name, new_version, &self.advisories);
+        print_yanked(name, new_version, self.yanked);
+
         info!("This will install the following binaries:");
         for file in bin_files {
             info!("  - {}", file.preview_bin());
@@ -142,7 +247,53 @@ impl ResolutionFetch {
                 info!("  - {}", file.preview_link());
             }
         }
+
+        if !self.extra_files.is_empty() {
+            info!("And install the following extra files:");
+            for file in &self.extra_files {
+                info!("  - {}", file.preview());
+            }
+        }
+    }
+}
+
+/// Choose how to run installed WASI modules: with the runtime configured,
+/// directly if supported by the host (e.g. through `binfmt_misc`), or with
+/// a known runtime found in `PATH`.
+///
+/// Return `None` if none is available.
+///
+/// This is a blocking function.
+fn resolve_wasi_launcher(opts: &Options) -> Option<WasiLauncher> {
+    let runtime = |program: &str| WasiLauncher::Runtime {
+        program: program.into(),
+        args: WasiLauncher::default_runtime_args(program)
+            .iter()
+            .map(|arg| CompactString::from(*arg))
+            .chain(opts.wasi_runtime_flags.iter().cloned())
+            .collect(),
+    };
+
+    if let Some(program) = &opts.wasi_runtime {
+        return Some(runtime(program));
+    }
+
+    match detect_wasi::detect_wasi_runability() {
+        Ok(true) => return Some(WasiLauncher::Direct),
+        Ok(false) => (),
+        Err(err) => debug!("Failed to detect whether WASI modules can be executed: {err}"),
     }
+
+    let paths = env::var_os("PATH")?;
+    ["wasmtime", "wasmer", "wasmedge"]
+        .into_iter()
+        .find(|program| {
+            env::split_paths(&paths).any(|dir| {
+                let path = dir.join(program);
+                path.is_file() || (cfg!(windows) && path.with_extension("exe").is_file())
+            })
+        })
+        .map(runtime)
 }
 
 impl ResolutionSource {
@@ -167,6 +318,10 @@ impl ResolutionSource {
         let name = &self.name;
         let version = &self.version;
 
+        if opts.bin_renames.contains_key(name) {
+            warn!("Renaming binaries is not supported by cargo-install, {name} will be installed with the original binary names");
+        }
+
         let cargo = env::var_os("CARGO")
             .map(Cow::Owned)
             .unwrap_or_else(|| Cow::Borrowed(OsStr::new("cargo")));
@@ -250,7 +405,32 @@ impl ResolutionSource {
         warn!(
             "The package {} v{} will be installed from source (with cargo)",
             self.name, self.version
-        )
+        );
+        info!("License: {}", DisplayLicense(self.license.as_deref()));
+        print_advisories(&self.name, &self.version, &self.advisories);
+        print_yanked(&self.name, &self.version, self.yanked);
+    }
+}
+
+fn print_advisories(name: &str, version: &dyn fmt::Display, advisories: &[Advisory]) {
+    for advisory in advisories {
+        warn!("{name} v{version} is affected by advisory {advisory}");
+    }
+}
+
+fn print_yanked(name: &str, version: &dyn fmt::Display, yanked: bool) {
+    if yanked {
+        warn!(
+            "{name} v{version} has been YANKED from the registry and is only installed because of --allow-yanked"
+        );
+    }
+}
+
+struct DisplayLicense<'a>(Option<&'a str>);
+
+impl fmt::Display for DisplayLicense<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0.unwrap_or("unspecified"))
     }
 }
 
@@ -0,0 +1,263 @@
+//! Post-install smoke test of the installed binaries.
+
+use std::{
+    collections::BTreeMap,
+    env,
+    io::Read,
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use command_group::CommandGroup;
+use compact_str::{format_compact, CompactString};
+use leon::Template;
+use semver::Version;
+use tracing::debug;
+
+use crate::errors::BinstallError;
+
+#[cfg(not(windows))]
+const KEPT_ENV_VARS: &[&str] = &["PATH"];
+#[cfg(windows)]
+const KEPT_ENV_VARS: &[&str] = &["PATH", "SystemRoot"];
+
+#[derive(Debug, Clone)]
+pub struct SmokeTest {
+    command: Vec<CompactString>,
+    check_version: bool,
+    timeout: Duration,
+}
+
+impl SmokeTest {
+    /// * `command` - command to run, the first element is the program.
+    ///   Each element is a template supporting the keys `bin` (path to the
+    ///   installed binary), `name` (installed name of the binary) and
+    ///   `version`.
+    /// * `check_version` - require the output of the command to contain the
+    ///   installed version.
+    ///
+    /// Return an error if `command` is empty or any of its templates is
+    /// invalid.
+    pub fn new(
+        command: Vec<CompactString>,
+        check_version: bool,
+        timeout: Duration,
+    ) -> Result<Self, BinstallError> {
+        if command.is_empty() {
+            return Err(BinstallError::InvalidSmokeTestCommand(
+                "empty command".into(),
+            ));
+        }
+
+        let values = BTreeMap::from([("bin", ""), ("name", ""), ("version", "")]);
+        for arg in &command {
+            render_arg(arg, &values).map_err(BinstallError::InvalidSmokeTestCommand)?;
+        }
+
+        Ok(Self {
+            command,
+            check_version,
+            timeout,
+        })
+    }
+
+    /// Run the command against the installed binary `path` in a sandboxed
+    /// environment: an empty working directory under `temp_dir`, with only
+    /// `PATH` (and `SystemRoot` on Windows, required to run most
+    /// executables) kept from the environment and no stdin.
+    ///
+    /// This is a blocking function.
+    pub(crate) fn run(
+        &self,
+        path: &Path,
+        name: &str,
+        version: &Version,
+        temp_dir: &Path,
+    ) -> Result<(), BinstallError> {
+        let fail = |reason: CompactString, stderr: &[u8]| BinstallError::SmokeTestFailed {
+            bin: name.into(),
+            reason,
+            stderr: String::from_utf8_lossy(stderr).trim_end().into(),
+        };
+
+        let version_str = version.to_string();
+        let bin = path.to_string_lossy();
+        let values = BTreeMap::from([
+            ("bin", &*bin),
+            ("name", name),
+            ("version", version_str.as_str()),
+        ]);
+
+        let command = self
+            .command
+            .iter()
+            .map(|arg| render_arg(arg, &values))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(BinstallError::InvalidSmokeTestCommand)?;
+        let Some((program, args)) = command.split_first() else {
+            return Err(BinstallError::InvalidSmokeTestCommand(
+                "empty command".into(),
+            ));
+        };
+
+        let sandbox = tempfile::tempdir_in(temp_dir)?;
+
+        debug!("Running smoke test for {name}: {command:?}");
+
+        let mut cmd = Command::new(program);
+        cmd.args(args)
+            .current_dir(sandbox.path())
+            .env_clear()
+            .env("HOME", sandbox.path())
+            .env("TMPDIR", sandbox.path())
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        for key in KEPT_ENV_VARS {
+            if let Some(value) = env::var_os(key) {
+                cmd.env(key, value);
+            }
+        }
+
+        // Spawn the command in its own process group (job object on Windows),
+        // so that processes it spawned can be killed along with it.
+        let mut child = cmd
+            .group_spawn()
+            .map_err(|err| fail(format_compact!("failed to spawn: {err}"), &[]))?;
+
+        // Drain the pipes on separate threads so that the child cannot block
+        // on a full pipe while we wait for it.
+        let stdout = drain(child.inner().stdout.take());
+        let stderr = drain(child.inner().stderr.take());
+
+        let deadline = Instant::now() + self.timeout;
+        let status = loop {
+            if let Some(status) = child.try_wait()? {
+                break Some(status);
+            }
+            if Instant::now() >= deadline {
+                break None;
+            }
+            thread::sleep(Duration::from_millis(10));
+        };
+
+        // Kill the whole group, including processes left running in the
+        // background, which would otherwise keep the pipes open.
+        child.kill().ok();
+        if status.is_none() {
+            child.wait()?;
+        }
+
+        let stdout = stdout.join().unwrap_or_default();
+        let stderr = stderr.join().unwrap_or_default();
+
+        match status {
+            None => Err(fail(
+                format_compact!("timed out after {}s", self.timeout.as_secs()),
+                &stderr,
+            )),
+            Some(status) if !status.success() => {
+                Err(fail(format_compact!("exited with {status}"), &stderr))
+            }
+            Some(_)
+                if self.check_version
+                    && !contains(&stdout, &version_str)
+                    && !contains(&stderr, &version_str) =>
+            {
+                Err(fail(
+                    format_compact!("output does not contain version {version_str}"),
+                    &stderr,
+                ))
+            }
+            Some(_) => Ok(()),
+        }
+    }
+}
+
+fn render_arg(arg: &str, values: &BTreeMap<&str, &str>) -> Result<String, CompactString> {
+    Template::parse(arg)
+        .map_err(|err| format_compact!("{err}"))?
+        .render(values)
+        .map_err(|err| format_compact!("{err}"))
+}
+
+fn drain(pipe: Option<impl Read + Send + 'static>) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        if let Some(mut pipe) = pipe {
+            pipe.read_to_end(&mut buf).ok();
+        }
+        buf
+    })
+}
+
+fn contains(haystack: &[u8], needle: &str) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle.as_bytes())
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use super::*;
+
+    fn smoke_test(command: &[&str]) -> SmokeTest {
+        SmokeTest::new(
+            command.iter().map(|arg| (*arg).into()).collect(),
+            true,
+            Duration::from_secs(10),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn invalid_command() {
+        for command in [&[][..], &["{ bin"], &["{ bin }", "{ unknown }"]] {
+            let err = SmokeTest::new(
+                command.iter().map(|arg| (*arg).into()).collect(),
+                true,
+                Duration::from_secs(10),
+            )
+            .unwrap_err();
+            assert!(matches!(err, BinstallError::InvalidSmokeTestCommand(_)));
+        }
+    }
+
+    #[test]
+    fn run() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let version = Version::new(1, 2, 3);
+        let sh = Path::new("/bin/sh");
+
+        smoke_test(&["{ bin }", "-c", "echo {name} { version }"])
+            .run(sh, "sh", &version, temp_dir.path())
+            .unwrap();
+
+        let err = smoke_test(&["{ bin }", "-c", "echo 0.0.1"])
+            .run(sh, "sh", &version, temp_dir.path())
+            .unwrap_err();
+        assert!(matches!(err, BinstallError::SmokeTestFailed { .. }));
+
+        // Processes left running in the background are killed
+        smoke_test(&["{ bin }", "-c", "sleep 60 & echo { version }"])
+            .run(sh, "sh", &version, temp_dir.path())
+            .unwrap();
+
+        let mut timeout = smoke_test(&["{ bin }", "-c", "sleep 60 & sleep 60"]);
+        timeout.timeout = Duration::from_millis(100);
+        let err = timeout
+            .run(sh, "sh", &version, temp_dir.path())
+            .unwrap_err();
+        assert!(matches!(err, BinstallError::SmokeTestFailed { .. }));
+
+        let err = smoke_test(&["{ bin }", "-c", "echo 1.2.3 && echo oops >&2 && exit 1"])
+            .run(sh, "sh", &version, temp_dir.path())
+            .unwrap_err();
+        match err {
+            BinstallError::SmokeTestFailed { stderr, .. } => assert_eq!(&*stderr, "oops"),
+            err => panic!("unexpected error {err}"),
+        }
+    }
+}
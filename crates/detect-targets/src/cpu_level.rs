@@ -0,0 +1,59 @@
+/// Detect the [x86-64 psABI microarchitecture level] supported by the host
+/// via CPUID, i.e. 1 to 4 for `x86-64-v1` to `x86-64-v4`.
+///
+/// Return `None` if the host is not x86_64.
+///
+/// [x86-64 psABI microarchitecture level]: https://gitlab.com/x86-psABIs/x86-64-ABI
+pub fn detect_x86_64_level() -> Option<u8> {
+    cfg_if::cfg_if! {
+        if #[cfg(target_arch = "x86_64")] {
+            Some(x86_64_level())
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+fn x86_64_level() -> u8 {
+    use std::arch::{is_x86_feature_detected as has, x86_64::__cpuid};
+
+    // LAHF/SAHF in 64-bit mode is not covered by `is_x86_feature_detected`,
+    // and MOVBE only is since Rust 1.67, newer than the MSRV.
+    //
+    // `__cpuid` is safe on newer toolchains.
+    #[allow(unused_unsafe)]
+    let (has_lahf_sahf, has_movbe) = unsafe {
+        (
+            __cpuid(0x8000_0000).eax >= 0x8000_0001 && __cpuid(0x8000_0001).ecx & 1 != 0,
+            __cpuid(1).ecx & (1 << 22) != 0,
+        )
+    };
+
+    let v2 = has_lahf_sahf
+        && has!("cmpxchg16b")
+        && has!("popcnt")
+        && has!("sse3")
+        && has!("ssse3")
+        && has!("sse4.1")
+        && has!("sse4.2");
+    // `is_x86_feature_detected` also checks that the OS saves the AVX states.
+    let v3 = v2
+        && has!("avx")
+        && has!("avx2")
+        && has!("bmi1")
+        && has!("bmi2")
+        && has!("f16c")
+        && has!("fma")
+        && has!("lzcnt")
+        && has_movbe
+        && has!("xsave");
+    let v4 = v3
+        && has!("avx512f")
+        && has!("avx512bw")
+        && has!("avx512cd")
+        && has!("avx512dq")
+        && has!("avx512vl");
+
+    1 + u8::from(v2) + u8::from(v3) + u8::from(v4)
+}
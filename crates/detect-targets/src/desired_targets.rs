@@ -1,12 +1,18 @@
-use crate::detect_targets;
+use crate::{detect_emulated_targets, detect_targets_with_info, Detection};
 
 use std::sync::Arc;
 
 use tokio::sync::SetOnce;
 
+#[derive(Debug)]
+struct Detected {
+    targets: Vec<String>,
+    detection: Detection,
+}
+
 #[derive(Debug)]
 enum DesiredTargetsInner {
-    AutoDetect(Arc<SetOnce<Vec<String>>>),
+    AutoDetect(Arc<SetOnce<Detected>>),
     Initialized(Vec<String>),
 }
 
@@ -18,12 +24,27 @@ impl DesiredTargets {
         Self(DesiredTargetsInner::Initialized(targets))
     }
 
-    fn auto_detect() -> Self {
+    fn auto_detect(include_emulated: bool) -> Self {
         let arc = Arc::new(SetOnce::new());
 
         let set_once = arc.clone();
         tokio::spawn(async move {
-            set_once.set(detect_targets().await).unwrap();
+            let mut detection = detect_targets_with_info().await;
+            if include_emulated {
+                for target in detect_emulated_targets() {
+                    if !detection.targets.iter().any(|t| t.target == target.target) {
+                        detection.targets.push(target);
+                    }
+                }
+            }
+            #[cfg(feature = "tracing")]
+            tracing::debug!("detect_targets_with_info()={detection:#?}");
+            set_once
+                .set(Detected {
+                    targets: detection.target_names(),
+                    detection,
+                })
+                .unwrap();
         });
 
         Self(DesiredTargetsInner::AutoDetect(arc))
@@ -34,7 +55,38 @@ impl DesiredTargets {
 
         match &self.0 {
             Initialized(targets) => targets,
-            AutoDetect(set_once) => set_once.wait().await,
+            AutoDetect(set_once) => &set_once.wait().await.targets,
+        }
+    }
+
+    /// Return the glibc version of the host as `(major, minor)` if the
+    /// targets are detected and include a `*-linux-gnu*` target.
+    ///
+    /// Return `None` if the targets are provided by the user, since they
+    /// might not be meant to run on the host.
+    pub async fn host_glibc_version(&self) -> Option<(u32, u32)> {
+        use DesiredTargetsInner::*;
+
+        match &self.0 {
+            Initialized(..) => None,
+            AutoDetect(set_once) => set_once
+                .wait()
+                .await
+                .detection
+                .libc
+                .as_ref()
+                .and_then(|libc| libc.glibc_version),
+        }
+    }
+
+    /// Return how the targets were detected, or `None` if they are provided
+    /// by the user.
+    pub async fn detection(&self) -> Option<&Detection> {
+        use DesiredTargetsInner::*;
+
+        match &self.0 {
+            Initialized(..) => None,
+            AutoDetect(set_once) => Some(&set_once.wait().await.detection),
         }
     }
 
@@ -57,9 +109,19 @@ impl DesiredTargets {
 /// it's pretty costy, it is recommended to run this fn ASAP and
 /// reuse the result.
 pub fn get_desired_targets(opts_targets: Option<Vec<String>>) -> DesiredTargets {
+    get_desired_targets_with_emulated(opts_targets, false)
+}
+
+/// Same as [`get_desired_targets`], but if `include_emulated` is true and
+/// the targets are detected, also append the targets that can be run through
+/// an interpreter registered in `binfmt_misc` after the native ones.
+pub fn get_desired_targets_with_emulated(
+    opts_targets: Option<Vec<String>>,
+    include_emulated: bool,
+) -> DesiredTargets {
     if let Some(targets) = opts_targets {
         DesiredTargets::initialized(targets)
     } else {
-        DesiredTargets::auto_detect()
+        DesiredTargets::auto_detect(include_emulated)
     }
 }
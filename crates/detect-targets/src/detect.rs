@@ -10,9 +10,13 @@ use tokio::process::Command;
 #[cfg(feature = "tracing")]
 use tracing::debug;
 
+use crate::{DetectedTarget, Detection, HostTargetSource, LibcInfo};
+
 cfg_if! {
     if #[cfg(any(target_os = "linux",  target_os = "android"))] {
         mod linux;
+        #[cfg(target_os = "linux")]
+        mod binfmt_misc;
     } else if #[cfg(target_os = "macos")] {
         mod macos;
     } else if #[cfg(target_os = "windows")] {
@@ -34,31 +38,97 @@ cfg_if! {
 /// Check [this issue](https://github.com/ryankurte/cargo-binstall/issues/155)
 /// for more information.
 pub async fn detect_targets() -> Vec<String> {
+    detect_targets_with_info().await.target_names()
+}
+
+/// Same as [`detect_targets`], but also return how the host target and
+/// each supported target were detected, as well as information on the
+/// libc of the host on Linux, which is useful for debugging.
+pub async fn detect_targets_with_info() -> Detection {
     let target = get_target_from_rustc().await;
     #[cfg(feature = "tracing")]
     debug!("get_target_from_rustc()={target:?}");
-    let target = target.unwrap_or_else(|| {
-        let target = guess_host_triple::guess_host_triple();
-        #[cfg(feature = "tracing")]
-        debug!("guess_host_triple::guess_host_triple()={target:?}");
-        target.unwrap_or(crate::TARGET).to_string()
-    });
+    let (target, host_target_source) = match target {
+        Some(target) => (target, HostTargetSource::Rustc),
+        None => {
+            let target = guess_host_triple::guess_host_triple();
+            #[cfg(feature = "tracing")]
+            debug!("guess_host_triple::guess_host_triple()={target:?}");
+            match target {
+                Some(target) => (target.to_string(), HostTargetSource::GuessHostTriple),
+                None => (crate::TARGET.to_string(), HostTargetSource::Compiled),
+            }
+        }
+    };
+
+    let host_reason = match host_target_source {
+        HostTargetSource::Rustc => "host target reported by rustc",
+        HostTargetSource::GuessHostTriple => "host target guessed from the running system",
+        HostTargetSource::Compiled => "target detect-targets is compiled for",
+    };
+
+    let (targets, libc) = detect_supported_targets(&target, host_reason).await;
 
+    Detection {
+        host_target: target,
+        host_target_source,
+        targets,
+        libc,
+        x86_64_level: crate::detect_x86_64_level(),
+    }
+}
+
+async fn detect_supported_targets(
+    target: &str,
+    host_reason: &'static str,
+) -> (Vec<DetectedTarget>, Option<LibcInfo>) {
     cfg_if! {
         if #[cfg(target_os = "macos")] {
-            let mut targets = vec![target];
-            targets.extend(macos::detect_alternative_targets(&targets[0]).await);
-            targets
+            let mut targets = vec![DetectedTarget::new(target, host_reason)];
+            targets.extend(macos::detect_alternative_targets(target).await);
+            (targets, None)
         } else if #[cfg(target_os = "windows")] {
-            let mut targets = vec![target];
-            targets.extend(windows::detect_alternative_targets(&targets[0]));
-            targets
+            let mut targets = vec![DetectedTarget::new(target, host_reason)];
+            targets.extend(windows::detect_alternative_targets(target));
+            (targets, None)
         } else if #[cfg(any(target_os = "linux", target_os = "android"))] {
             // Linux is a bit special, since the result from `guess_host_triple`
             // might be wrong about whether glibc or musl is used.
-            linux::detect_targets(target).await
+            let (targets, libc) = linux::detect_targets(target, host_reason).await;
+            (targets, Some(libc))
+        } else {
+            (vec![DetectedTarget::new(target, host_reason)], None)
+        }
+    }
+}
+
+/// Detect the version of glibc on the host as `(major, minor)`.
+///
+/// Return `None` if the host does not use glibc or the version cannot be
+/// detected.
+pub async fn detect_glibc_version() -> Option<(u32, u32)> {
+    cfg_if! {
+        if #[cfg(target_os = "linux")] {
+            linux::detect_glibc_version().await
+        } else {
+            None
+        }
+    }
+}
+
+/// Detect the foreign targets that can be run on the host through an
+/// interpreter registered in `binfmt_misc`, e.g. `qemu-user`.
+///
+/// These are not included in [`detect_targets`] since they are much slower
+/// than the native targets and might not work as well.
+///
+/// Return an empty `Vec` on platforms other than Linux.
+pub fn detect_emulated_targets() -> Vec<DetectedTarget> {
+    cfg_if! {
+        if #[cfg(target_os = "linux")] {
+            binfmt_misc::detect_emulated_targets()
         } else {
-            vec![target]
+            Vec::new()
         }
     }
 }
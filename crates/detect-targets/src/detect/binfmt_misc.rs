@@ -0,0 +1,265 @@
+//! Detection of foreign targets that can run through an interpreter, e.g.
+//! `qemu-user`, registered in `binfmt_misc`.
+
+use std::{fs, path::Path};
+
+use crate::DetectedTarget;
+
+const BINFMT_MISC: &str = "/proc/sys/fs/binfmt_misc";
+
+/// Linux targets that can be emulated for an ELF machine type, and the
+/// dynamic loader needed to run its glibc binaries.
+struct Emulated {
+    e_machine: u16,
+    is_64: bool,
+    little_endian: bool,
+    arches: &'static [&'static str],
+    ld: &'static str,
+}
+
+const EMULATED: &[Emulated] = &[
+    Emulated {
+        e_machine: 62,
+        is_64: true,
+        little_endian: true,
+        arches: &["x86_64"],
+        ld: "ld-linux-x86-64.so.2",
+    },
+    Emulated {
+        e_machine: 3,
+        is_64: false,
+        little_endian: true,
+        arches: &["i686", "i586"],
+        ld: "ld-linux.so.2",
+    },
+    Emulated {
+        e_machine: 183,
+        is_64: true,
+        little_endian: true,
+        arches: &["aarch64"],
+        ld: "ld-linux-aarch64.so.1",
+    },
+    Emulated {
+        e_machine: 40,
+        is_64: false,
+        little_endian: true,
+        arches: &["armv7", "arm"],
+        ld: "ld-linux-armhf.so.3",
+    },
+    Emulated {
+        e_machine: 243,
+        is_64: true,
+        little_endian: true,
+        arches: &["riscv64gc"],
+        ld: "ld-linux-riscv64-lp64d.so.1",
+    },
+    Emulated {
+        e_machine: 21,
+        is_64: true,
+        little_endian: true,
+        arches: &["powerpc64le"],
+        ld: "ld64.so.2",
+    },
+    Emulated {
+        e_machine: 22,
+        is_64: true,
+        little_endian: false,
+        arches: &["s390x"],
+        ld: "ld64.so.1",
+    },
+    Emulated {
+        e_machine: 258,
+        is_64: true,
+        little_endian: true,
+        arches: &["loongarch64"],
+        ld: "ld-linux-loongarch-lp64d.so.1",
+    },
+];
+
+/// An enabled `binfmt_misc` entry matching ELF executables.
+#[derive(Debug, Eq, PartialEq)]
+struct ElfEntry {
+    interpreter: String,
+    e_machine: u16,
+    is_64: bool,
+    little_endian: bool,
+}
+
+/// Parse an entry in `/proc/sys/fs/binfmt_misc`, e.g.
+///
+/// ```plain
+/// enabled
+/// interpreter /usr/bin/qemu-x86_64-static
+/// flags: F
+/// offset 0
+/// magic 7f454c4602010100000000000000000002003e00
+/// mask fffffffffffefe00fffffffffffffffffeffffff
+/// ```
+fn parse_entry(content: &str) -> Option<ElfEntry> {
+    let mut lines = content.lines();
+    if lines.next()? != "enabled" {
+        return None;
+    }
+
+    let (mut interpreter, mut flags, mut offset, mut magic) = (None, "", None, None);
+    for line in lines {
+        let (key, value) = line.split_once(' ')?;
+        match key {
+            "interpreter" => interpreter = Some(value),
+            "flags:" => flags = value,
+            "offset" => offset = Some(value),
+            "magic" => magic = Some(decode_hex(value)?),
+            _ => (),
+        }
+    }
+
+    let (interpreter, magic) = (interpreter?, magic?);
+    if offset != Some("0") || magic.get(..4)? != b"\x7fELF" {
+        return None;
+    }
+    // With the `F` flag, the interpreter is opened on registration, so it
+    // might not exist in the current mount namespace, e.g. in containers.
+    if !flags.contains('F') && !Path::new(interpreter).exists() {
+        return None;
+    }
+
+    let little_endian = *magic.get(5)? == 1;
+    let e_machine = [*magic.get(18)?, *magic.get(19)?];
+
+    Some(ElfEntry {
+        interpreter: interpreter.to_owned(),
+        e_machine: if little_endian {
+            u16::from_le_bytes(e_machine)
+        } else {
+            u16::from_be_bytes(e_machine)
+        },
+        is_64: *magic.get(4)? == 2,
+        little_endian,
+    })
+}
+
+fn decode_hex(s: &str) -> Option<Vec<u8>> {
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+fn has_ld(arch: &str, ld: &str) -> bool {
+    [
+        format!("/lib/{ld}"),
+        format!("/lib64/{ld}"),
+        format!("/lib/{arch}-linux-gnu/{ld}"),
+        format!("/usr/{arch}-linux-gnu/lib/{ld}"),
+        format!("/usr/{arch}-linux-gnu/lib64/{ld}"),
+    ]
+    .iter()
+    .any(|path| Path::new(path).exists())
+}
+
+/// Return the targets that can run through the interpreters registered in
+/// `binfmt_misc`.
+///
+/// The musl targets are always returned since they are statically linked,
+/// glibc ones are only returned if the dynamic loader for the architecture
+/// is installed.
+pub(super) fn detect_emulated_targets() -> Vec<DetectedTarget> {
+    if fs::read_to_string(Path::new(BINFMT_MISC).join("status"))
+        .map_or(true, |status| status.trim() != "enabled")
+    {
+        return Vec::new();
+    }
+
+    let entries = match fs::read_dir(BINFMT_MISC) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+
+    let mut targets = Vec::new();
+    for entry in entries.flatten() {
+        let entry = match fs::read_to_string(entry.path())
+            .ok()
+            .as_deref()
+            .and_then(parse_entry)
+        {
+            Some(entry) => entry,
+            None => continue,
+        };
+
+        let emulated = match EMULATED.iter().find(|emulated| {
+            emulated.e_machine == entry.e_machine
+                && emulated.is_64 == entry.is_64
+                && emulated.little_endian == entry.little_endian
+        }) {
+            Some(emulated) => emulated,
+            None => continue,
+        };
+
+        let reason = format!(
+            "emulated by {} registered in binfmt_misc",
+            entry.interpreter
+        );
+        for arch in emulated.arches {
+            let abi = if *arch == "armv7" || *arch == "arm" {
+                "eabihf"
+            } else {
+                ""
+            };
+            if has_ld(arch, emulated.ld) {
+                targets.push(DetectedTarget::new(
+                    format!("{arch}-unknown-linux-gnu{abi}"),
+                    reason.clone(),
+                ));
+            }
+            targets.push(DetectedTarget::new(
+                format!("{arch}-unknown-linux-musl{abi}"),
+                reason.clone(),
+            ));
+        }
+    }
+
+    targets
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_entry() {
+        let entry = parse_entry(
+            "enabled
+interpreter /usr/bin/qemu-x86_64-static
+flags: F
+offset 0
+magic 7f454c4602010100000000000000000002003e00
+mask fffffffffffefe00fffffffffffffffffeffffff
+",
+        );
+        assert_eq!(
+            entry,
+            Some(ElfEntry {
+                interpreter: "/usr/bin/qemu-x86_64-static".to_owned(),
+                e_machine: 62,
+                is_64: true,
+                little_endian: true,
+            })
+        );
+
+        let wasi = "enabled
+interpreter /usr/bin/wasmtime
+flags: F
+offset 0
+magic 0061736d
+";
+        assert_eq!(parse_entry(wasi), None);
+
+        let disabled = "disabled
+interpreter /usr/bin/qemu-aarch64-static
+flags: F
+offset 0
+magic 7f454c460201010000000000000000000200b700
+";
+        assert_eq!(parse_entry(disabled), None);
+    }
+}
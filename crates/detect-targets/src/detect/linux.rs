@@ -7,19 +7,24 @@ use tokio::{process::Command, task};
 #[cfg(feature = "tracing")]
 use tracing::debug;
 
-pub(super) async fn detect_targets(target: String) -> Vec<String> {
+use crate::{parse_glibc_version, DetectedTarget, LibcFlavour, LibcInfo};
+
+pub(super) async fn detect_targets(
+    target: &str,
+    host_reason: &'static str,
+) -> (Vec<DetectedTarget>, LibcInfo) {
     let (_, postfix) = target
         .rsplit_once('-')
         .expect("unwrap: target always has a -");
 
-    let (abi, libc) = if let Some(abi) = postfix.strip_prefix("musl") {
-        (abi, Libc::Musl)
+    let (abi, flavour) = if let Some(abi) = postfix.strip_prefix("musl") {
+        (abi, LibcFlavour::Musl)
     } else if let Some(abi) = postfix.strip_prefix("gnu") {
-        (abi, Libc::Gnu)
+        (abi, LibcFlavour::Gnu)
     } else if let Some(abi) = postfix.strip_prefix("android") {
-        (abi, Libc::Android)
+        (abi, LibcFlavour::Android)
     } else {
-        (postfix, Libc::Unknown)
+        (postfix, LibcFlavour::Unknown)
     };
 
     let cpu_arch = target
@@ -27,11 +32,24 @@ pub(super) async fn detect_targets(target: String) -> Vec<String> {
         .expect("unwrap: target always has a - for cpu_arch")
         .0;
 
+    let mut libc = LibcInfo {
+        flavour,
+        has_glibc: false,
+        glibc_version: None,
+        gcompat: false,
+        matched_probe_paths: Vec::new(),
+    };
+
     // For android the `-unknown-` is omitted, for alpine it has `-alpine-`
     // instead of `-unknown-`.
-    let musl_fallback_target = || format!("{cpu_arch}-unknown-linux-musl{abi}");
+    let musl_fallback_target = || {
+        DetectedTarget::new(
+            format!("{cpu_arch}-unknown-linux-musl{abi}"),
+            "statically linked musl binaries run on any Linux",
+        )
+    };
 
-    match libc {
+    let targets = match flavour {
         // guess_host_triple cannot detect whether the system is using glibc,
         // musl libc or other libc.
         //
@@ -39,7 +57,7 @@ pub(super) async fn detect_targets(target: String) -> Vec<String> {
         // and run glibc programs.
         //
         // As such, we need to launch the test ourselves.
-        Libc::Gnu | Libc::Musl => {
+        LibcFlavour::Gnu | LibcFlavour::Musl => {
             let handles: Vec<_> = {
                 let cpu_arch_suffix = cpu_arch.replace('_', "-");
                 let filename = format!("ld-linux-{cpu_arch_suffix}.so.2");
@@ -75,38 +93,90 @@ pub(super) async fn detect_targets(target: String) -> Vec<String> {
 
                 probe_paths
                     .into_iter()
-                    .map(|p| AutoAbortHandle(tokio::spawn(is_gnu_ld(p))))
+                    .map(|p| (p.clone(), AutoAbortHandle(tokio::spawn(probe_glibc(p)))))
                     .collect()
             };
 
-            let has_glibc = async move {
-                for mut handle in handles {
-                    if let Ok(true) = (&mut handle.0).await {
-                        return true;
-                    }
+            for (path, mut handle) in handles {
+                if let Ok(Some(flavor)) = (&mut handle.0).await {
+                    libc.gcompat |= flavor == LdFlavor::Gcompat;
+                    libc.matched_probe_paths.push(path);
                 }
+            }
 
-                false
+            libc.has_glibc = !libc.matched_probe_paths.is_empty();
+            if libc.has_glibc {
+                libc.glibc_version = detect_glibc_version().await;
             }
-            .await;
 
-            [
-                has_glibc.then(|| format!("{cpu_arch}-unknown-linux-gnu{abi}")),
-                Some(musl_fallback_target()),
-            ]
+            let gnu_target = libc.matched_probe_paths.first().map(|path| {
+                DetectedTarget::new(
+                    format!("{cpu_arch}-unknown-linux-gnu{abi}"),
+                    if libc.gcompat {
+                        format!("glibc provided by gcompat found at {path}")
+                    } else {
+                        format!("glibc found at {path}")
+                    },
+                )
+            });
+
+            [gnu_target, Some(musl_fallback_target())]
+        }
+        LibcFlavour::Android | LibcFlavour::Unknown => [
+            Some(DetectedTarget::new(target, host_reason)),
+            Some(musl_fallback_target()),
+        ],
+    };
+
+    (targets.into_iter().flatten().collect(), libc)
+}
+
+/// Detect the version of glibc on the host as `(major, minor)`.
+pub(super) async fn detect_glibc_version() -> Option<(u32, u32)> {
+    // `ldd --version` prints e.g. `ldd (Debian GLIBC 2.36-9) 2.36` on its
+    // first line, `getconf GNU_LIBC_VERSION` prints `glibc 2.36`.
+    for (cmd, arg) in [("ldd", "--version"), ("getconf", "GNU_LIBC_VERSION")] {
+        let output = match Command::new(cmd)
+            .arg(arg)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .await
+        {
+            Ok(output) if output.status.success() => output,
+            _res => {
+                #[cfg(feature = "tracing")]
+                debug!("Running `{cmd} {arg}`: {_res:?}");
+                continue;
+            }
+        };
+
+        let version = str::from_utf8(&output.stdout)
+            .ok()
+            .and_then(|stdout| stdout.lines().next())
+            .and_then(|line| line.split_whitespace().last())
+            .and_then(parse_glibc_version);
+
+        #[cfg(feature = "tracing")]
+        debug!("`{cmd} {arg}`: glibc version={version:?}");
+
+        if version.is_some() {
+            return version;
         }
-        Libc::Android | Libc::Unknown => [Some(target.clone()), Some(musl_fallback_target())],
     }
-    .into_iter()
-    .flatten()
-    .collect()
+
+    None
 }
 
-async fn is_gnu_ld(cmd: String) -> bool {
-    get_ld_flavor(&cmd).await == Some(Libc::Gnu)
+/// Return `Some` if `cmd` is a dynamic loader or libc that supports glibc
+/// programs.
+async fn probe_glibc(cmd: String) -> Option<LdFlavor> {
+    get_ld_flavor(&cmd)
+        .await
+        .filter(|flavor| *flavor != LdFlavor::Musl)
 }
 
-async fn get_ld_flavor(cmd: &str) -> Option<Libc> {
+async fn get_ld_flavor(cmd: &str) -> Option<LdFlavor> {
     let Output {
         status,
         stdout,
@@ -138,16 +208,16 @@ You are not meant to run this directly.
     if status.success() {
         // Executing glibc ldd or /lib/ld-linux-{cpu_arch}.so.1 will always
         // succeeds.
-        (stdout.contains("GLIBC") || stdout.contains("GNU libc")).then_some(Libc::Gnu)
+        (stdout.contains("GLIBC") || stdout.contains("GNU libc")).then_some(LdFlavor::Gnu)
     } else if status.code() == Some(1) {
         // On Alpine, executing both the gcompat glibc and the ldd and
         // /lib/ld-musl-{cpu_arch}.so.1 will fail with exit status 1.
         if stdout == ALPINE_GCOMPAT {
             // Alpine's gcompat package will output ALPINE_GCOMPAT to stdout
-            Some(Libc::Gnu)
+            Some(LdFlavor::Gcompat)
         } else if stderr.contains("musl libc") {
             // Alpine/s ldd and musl dynlib will output to stderr
-            Some(Libc::Musl)
+            Some(LdFlavor::Musl)
         } else {
             None
         }
@@ -166,18 +236,18 @@ You are not meant to run this directly.
         #[cfg(feature = "tracing")]
         debug!("`{cmd} --version`: status={status}");
 
-        status.success().then_some(Libc::Gnu)
+        status.success().then_some(LdFlavor::Gnu)
     } else {
         None
     }
 }
 
 #[derive(Eq, PartialEq)]
-enum Libc {
+enum LdFlavor {
     Gnu,
+    /// Alpine's glibc compatibility layer.
+    Gcompat,
     Musl,
-    Android,
-    Unknown,
 }
 
 struct AutoAbortHandle<T>(task::JoinHandle<T>);
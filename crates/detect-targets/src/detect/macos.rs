@@ -2,6 +2,8 @@ use std::process::Stdio;
 
 use tokio::process::Command;
 
+use crate::DetectedTarget;
+
 const AARCH64: &str = "aarch64-apple-darwin";
 const X86: &str = "x86_64-apple-darwin";
 /// https://doc.rust-lang.org/nightly/rustc/platform-support/x86_64h-apple-darwin.html
@@ -33,7 +35,11 @@ async fn is_arch_supported(arch_name: &str) -> bool {
         .unwrap_or(false)
 }
 
-pub(super) async fn detect_alternative_targets(target: &str) -> impl Iterator<Item = String> {
+pub(super) async fn detect_alternative_targets(
+    target: &str,
+) -> impl Iterator<Item = DetectedTarget> {
+    const UNIVERSAL_REASON: &str = "universal binaries contain a native executable";
+
     match target {
         AARCH64 => {
             // Spawn `arch` in parallel (probably from different threads if
@@ -45,23 +51,36 @@ pub(super) async fn detect_alternative_targets(target: &str) -> impl Iterator<It
             let x86_64_task = tokio::spawn(is_arch_supported("x86_64"));
             [
                 // Prefer universal as it provides native arm executable
-                Some(UNIVERSAL),
-                Some(UNIVERSAL2),
+                Some((UNIVERSAL, UNIVERSAL_REASON)),
+                Some((UNIVERSAL2, UNIVERSAL_REASON)),
                 // Prefer x86h since it is more optimized
-                x86_64h_task.await.unwrap().then_some(X86H),
-                x86_64_task.await.unwrap().then_some(X86),
+                x86_64h_task
+                    .await
+                    .unwrap()
+                    .then_some((X86H, "x86_64h is supported by Rosetta 2")),
+                x86_64_task
+                    .await
+                    .unwrap()
+                    .then_some((X86, "x86_64 is supported by Rosetta 2")),
             ]
         }
         X86 => [
-            is_arch_supported("x86_64h").await.then_some(X86H),
-            Some(UNIVERSAL),
-            Some(UNIVERSAL2),
+            is_arch_supported("x86_64h")
+                .await
+                .then_some((X86H, "the CPU supports x86_64h")),
+            Some((UNIVERSAL, UNIVERSAL_REASON)),
+            Some((UNIVERSAL2, UNIVERSAL_REASON)),
+            None,
+        ],
+        X86H => [
+            Some((X86, "x86_64h CPUs also run x86_64")),
+            Some((UNIVERSAL, UNIVERSAL_REASON)),
+            Some((UNIVERSAL2, UNIVERSAL_REASON)),
             None,
         ],
-        X86H => [Some(X86), Some(UNIVERSAL), Some(UNIVERSAL2), None],
         _ => [None, None, None, None],
     }
     .into_iter()
     .flatten()
-    .map(ToString::to_string)
+    .map(|(target, reason)| DetectedTarget::new(target, reason))
 }
@@ -11,6 +11,8 @@ use windows_sys::Win32::{
     },
 };
 
+use crate::DetectedTarget;
+
 struct LibraryHandle(HMODULE);
 
 impl LibraryHandle {
@@ -63,7 +65,7 @@ fn is_arch_supported(arch: IMAGE_FILE_MACHINE) -> bool {
     is_arch_supported_inner(arch).unwrap_or(false)
 }
 
-pub(super) fn detect_alternative_targets(target: &str) -> impl Iterator<Item = String> {
+pub(super) fn detect_alternative_targets(target: &str) -> impl Iterator<Item = DetectedTarget> {
     let (prefix, abi) = target
         .rsplit_once('-')
         .expect("unwrap: target always has a -");
@@ -73,12 +75,14 @@ pub(super) fn detect_alternative_targets(target: &str) -> impl Iterator<Item = S
         .expect("unwrap: target always has at least two -")
         .0;
 
-    let msvc_fallback_target = (abi != "msvc").then(|| format!("{prefix}-msvc"));
+    let msvc_fallback_target = (abi != "msvc")
+        .then(|| DetectedTarget::new(format!("{prefix}-msvc"), "msvc binaries run on any Windows"));
 
     let gnu_fallback_targets = (abi == "msvc")
         .then(|| [format!("{prefix}-gnu"), format!("{prefix}-gnullvm")])
         .into_iter()
-        .flatten();
+        .flatten()
+        .map(|target| DetectedTarget::new(target, "gnu binaries run on any Windows"));
 
     let x64_fallback_targets = (arch != "x86_64" && is_arch_supported(IMAGE_FILE_MACHINE_AMD64))
         .then_some([
@@ -88,7 +92,7 @@ pub(super) fn detect_alternative_targets(target: &str) -> impl Iterator<Item = S
         ])
         .into_iter()
         .flatten()
-        .map(ToString::to_string);
+        .map(|target| DetectedTarget::new(target, "x86_64 emulation is supported by Windows"));
 
     let x86_fallback_targets = (arch != "x86" && is_arch_supported(IMAGE_FILE_MACHINE_I386))
         .then_some([
@@ -101,7 +105,7 @@ pub(super) fn detect_alternative_targets(target: &str) -> impl Iterator<Item = S
         ])
         .into_iter()
         .flatten()
-        .map(ToString::to_string);
+        .map(|target| DetectedTarget::new(target, "x86 is supported by WoW64"));
 
     let arm32_fallback_targets = (arch != "thumbv7a" && is_arch_supported(IMAGE_FILE_MACHINE_ARM))
         .then_some([
@@ -111,7 +115,7 @@ pub(super) fn detect_alternative_targets(target: &str) -> impl Iterator<Item = S
         ])
         .into_iter()
         .flatten()
-        .map(ToString::to_string);
+        .map(|target| DetectedTarget::new(target, "arm32 is supported by WoW64"));
 
     let arm64_fallback_targets = (arch != "aarch64" && is_arch_supported(IMAGE_FILE_MACHINE_ARM64))
         .then_some([
@@ -121,7 +125,7 @@ pub(super) fn detect_alternative_targets(target: &str) -> impl Iterator<Item = S
         ])
         .into_iter()
         .flatten()
-        .map(ToString::to_string);
+        .map(|target| DetectedTarget::new(target, "aarch64 is supported by Windows"));
 
     msvc_fallback_target
         .into_iter()
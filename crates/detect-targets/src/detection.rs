@@ -0,0 +1,122 @@
+use std::borrow::Cow;
+
+#[cfg(feature = "json")]
+use serde::{Serialize, Serializer};
+
+/// Result of [`detect_targets_with_info`](crate::detect_targets_with_info),
+/// explaining how each target was detected.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "json", derive(Serialize))]
+#[non_exhaustive]
+pub struct Detection {
+    /// Host target the detection started from.
+    pub host_target: String,
+    /// How `host_target` was obtained.
+    pub host_target_source: HostTargetSource,
+    /// Targets supported, in the order of preference.
+    pub targets: Vec<DetectedTarget>,
+    /// Information on the libc of the host, only available on Linux.
+    pub libc: Option<LibcInfo>,
+    /// x86-64 psABI microarchitecture level of the host, from 1 to 4.
+    pub x86_64_level: Option<u8>,
+}
+
+impl Detection {
+    /// Return the names of the supported targets, in the order of preference.
+    pub fn target_names(&self) -> Vec<String> {
+        self.targets
+            .iter()
+            .map(|target| target.target.clone())
+            .collect()
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize), serde(rename_all = "kebab-case"))]
+pub enum HostTargetSource {
+    /// Output of `$CARGO -vV` or `rustc -vV`.
+    Rustc,
+    /// Guessed from the running system by `guess_host_triple`.
+    GuessHostTriple,
+    /// Target `detect-targets` is compiled for.
+    Compiled,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "json", derive(Serialize))]
+#[non_exhaustive]
+pub struct DetectedTarget {
+    pub target: String,
+    /// Why the target was added.
+    pub reason: Cow<'static, str>,
+}
+
+impl DetectedTarget {
+    pub(crate) fn new(target: impl Into<String>, reason: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            target: target.into(),
+            reason: reason.into(),
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "json", derive(Serialize), serde(rename_all = "kebab-case"))]
+pub enum LibcFlavour {
+    Gnu,
+    Musl,
+    Android,
+    Unknown,
+}
+
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "json", derive(Serialize))]
+#[non_exhaustive]
+pub struct LibcInfo {
+    /// Libc flavour of the host target.
+    pub flavour: LibcFlavour,
+    /// Whether glibc programs can run on the host.
+    pub has_glibc: bool,
+    /// Version of glibc as `(major, minor)`, if glibc is available and the
+    /// version can be detected.
+    #[cfg_attr(feature = "json", serde(serialize_with = "serialize_glibc_version"))]
+    pub glibc_version: Option<(u32, u32)>,
+    /// Whether glibc is provided by Alpine's gcompat.
+    pub gcompat: bool,
+    /// Dynamic loaders and libc probed that support glibc programs.
+    pub matched_probe_paths: Vec<String>,
+}
+
+#[cfg(feature = "json")]
+fn serialize_glibc_version<S: Serializer>(
+    version: &Option<(u32, u32)>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match version {
+        Some((major, minor)) => serializer.collect_str(&format_args!("{major}.{minor}")),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Parse a glibc version, e.g. `2.35`, `2.2.5` or `2.35-0ubuntu3`, into
+/// `(major, minor)`.
+pub fn parse_glibc_version(s: &str) -> Option<(u32, u32)> {
+    let (major, rest) = s.split_once('.')?;
+    let minor = rest.split(|c: char| !c.is_ascii_digit()).next()?;
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_glibc_version() {
+        assert_eq!(parse_glibc_version("2.36"), Some((2, 36)));
+        assert_eq!(parse_glibc_version("2.17"), Some((2, 17)));
+        assert_eq!(parse_glibc_version("2.2.5"), Some((2, 2)));
+        assert_eq!(parse_glibc_version("2.35-0ubuntu3"), Some((2, 35)));
+        assert_eq!(parse_glibc_version("GLIBC"), None);
+        assert_eq!(parse_glibc_version("PRIVATE"), None);
+    }
+}
@@ -15,9 +15,14 @@
 //! This crate provides two API:
 //!  - [`detect_targets`] provides the API to get the target
 //!    at runtime, but the code is run on the current thread.
+//!    [`detect_targets_with_info`] additionally explains why each
+//!    target was detected, which is also printed by
+//!    `detect-targets --json` when built with the `json` feature.
 //!  - [`get_desired_targets`] provides the API to either
 //!    use override provided by the users, or run [`detect_targets`]
 //!    in the background using [`tokio::spawn`].
+//!    [`get_desired_targets_with_emulated`] can additionally append the
+//!    foreign targets returned by [`detect_emulated_targets`].
 //!
 //! # Example
 //!
@@ -65,10 +70,20 @@
 //! ```
 
 mod detect;
-pub use detect::detect_targets;
+pub use detect::{
+    detect_emulated_targets, detect_glibc_version, detect_targets, detect_targets_with_info,
+};
+
+mod cpu_level;
+pub use cpu_level::detect_x86_64_level;
+
+mod detection;
+pub use detection::{
+    parse_glibc_version, DetectedTarget, Detection, HostTargetSource, LibcFlavour, LibcInfo,
+};
 
 mod desired_targets;
-pub use desired_targets::{get_desired_targets, DesiredTargets};
+pub use desired_targets::{get_desired_targets, get_desired_targets_with_emulated, DesiredTargets};
 
 /// Compiled target triple, used as default for binary fetching
 pub const TARGET: &str = env!("TARGET");
@@ -1,6 +1,6 @@
-use std::io;
+use std::{env, io};
 
-use detect_targets::detect_targets;
+use detect_targets::{detect_emulated_targets, detect_targets_with_info};
 use tokio::runtime;
 
 fn main() -> io::Result<()> {
@@ -10,13 +10,46 @@ fn main() -> io::Result<()> {
         .with_writer(std::io::stderr)
         .init();
 
-    let targets = runtime::Builder::new_current_thread()
+    let (mut json, mut emulated) = (false, false);
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--emulated" => emulated = true,
+            arg => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!(
+                        "unexpected argument {arg}, usage: detect-targets [--json] [--emulated]"
+                    ),
+                ))
+            }
+        }
+    }
+
+    let mut detection = runtime::Builder::new_current_thread()
         .enable_all()
         .build()?
-        .block_on(detect_targets());
+        .block_on(detect_targets_with_info());
+    if emulated {
+        detection.targets.extend(detect_emulated_targets());
+    }
+
+    if json {
+        #[cfg(feature = "json")]
+        {
+            serde_json::to_writer_pretty(io::stdout().lock(), &detection)?;
+            println!();
+        }
 
-    for target in targets {
-        println!("{target}");
+        #[cfg(not(feature = "json"))]
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "--json requires detect-targets to be built with the `json` feature",
+        ));
+    } else {
+        for target in detection.targets {
+            println!("{}", target.target);
+        }
     }
 
     Ok(())
@@ -0,0 +1,32 @@
+#!/bin/bash
+
+set -euxo pipefail
+
+unset CARGO_INSTALL_ROOT
+
+# Copy it to bin to test use of env var `CARGO`
+cp "$1" "$CARGO_HOME/bin/"
+
+# Install `cargo-watch` as `watcher`
+cargo binstall --no-confirm --rename cargo-watch=watcher cargo-watch@8.4.0
+
+if command -v cargo-watch >/dev/null 2>&1; then
+  echo "cargo-watch should have been renamed"
+  exit 1
+fi
+watcher --version
+
+# Renames are kept when upgrading
+cargo binstall --no-confirm cargo-watch@8.5.3
+watcher --version | grep -q '8.5.3'
+if command -v cargo-watch >/dev/null 2>&1; then
+  echo "cargo-watch should have been renamed"
+  exit 1
+fi
+
+# cargo-uninstall removes the renamed binary
+cargo uninstall cargo-watch
+if command -v watcher >/dev/null 2>&1; then
+  echo "watcher should have been uninstalled"
+  exit 1
+fi
@@ -0,0 +1,21 @@
+#!/bin/bash
+
+set -euxo pipefail
+
+unset CARGO_INSTALL_ROOT
+
+# Install an old version, then upgrade it
+"$1" binstall --no-confirm cargo-binstall@0.11.1
+"$1" binstall --no-confirm cargo-binstall@0.12.0
+grep -q 'cargo-binstall 0.12.0' "$CARGO_HOME/.crates.toml"
+
+# Roll back to the previously installed version
+"$1" binstall --rollback cargo-binstall
+grep -q 'cargo-binstall 0.11.1' "$CARGO_HOME/.crates.toml"
+grep -q '"current_version":"0.11.1"' "$CARGO_HOME/binstall/crates-v1.json"
+
+# The backup is consumed by the rollback
+if "$1" binstall --rollback cargo-binstall; then
+    echo "Expected rollback without backup to fail"
+    exit 1
+fi
@@ -0,0 +1,24 @@
+#!/bin/bash
+
+set -euxo pipefail
+
+unset CARGO_INSTALL_ROOT
+
+# Install two versions side by side
+"$1" binstall --no-confirm --versioned cargo-binstall@0.11.1
+"$1" binstall --no-confirm --versioned cargo-binstall@0.12.0
+
+test -x "$CARGO_HOME/bin/cargo-binstall-v0.11.1"
+test -x "$CARGO_HOME/bin/cargo-binstall-v0.12.0"
+[ "$(readlink "$CARGO_HOME/bin/cargo-binstall")" = "cargo-binstall-v0.12.0" ]
+
+# Switch back to the older one
+"$1" binstall --use cargo-binstall@0.11.1
+[ "$(readlink "$CARGO_HOME/bin/cargo-binstall")" = "cargo-binstall-v0.11.1" ]
+grep -q 'cargo-binstall 0.11.1' "$CARGO_HOME/.crates.toml"
+
+# Switching to a version not installed fails
+if "$1" binstall --use cargo-binstall@0.13.0; then
+    echo "Expected switching to a version not installed to fail"
+    exit 1
+fi